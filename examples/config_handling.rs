@@ -188,6 +188,15 @@ fn main() -> Result<()> {
         )
     }
 
+    if command_line_args.transfer_sync_batch.is_some() {
+        assert_eq!(
+            command_line_args.transfer_sync_batch,
+            config.transfer_sync_batch
+        )
+    } else {
+        assert_eq!(file_config.transfer_sync_batch, config.transfer_sync_batch)
+    }
+
     clear_disk_config()?;
 
     Ok(())
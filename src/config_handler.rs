@@ -115,6 +115,11 @@ pub struct Config {
     /// Duration of a UPnP port mapping.
     #[structopt(long)]
     pub upnp_lease_duration: Option<u32>,
+    /// Number of appends to a transfer store between syncs to disk. By default every append is
+    /// synced, so that no confirmed transfer is lost on a power loss. Larger batches write faster,
+    /// but up to that many appends, minus one, can be lost on a power loss.
+    #[structopt(long)]
+    pub transfer_sync_batch: Option<usize>,
     #[structopt(skip)]
     #[allow(missing_docs)]
     pub network_config: NetworkConfig,
@@ -236,6 +241,10 @@ impl Config {
         if let Some(upnp_lease_duration) = config.upnp_lease_duration {
             self.network_config.upnp_lease_duration = Some(upnp_lease_duration);
         }
+
+        if let Some(transfer_sync_batch) = config.transfer_sync_batch {
+            self.transfer_sync_batch = Some(transfer_sync_batch);
+        }
     }
 
     /// The address to be credited when this node farms SafeCoin.
@@ -253,6 +262,11 @@ impl Config {
         self.max_capacity.unwrap_or(DEFAULT_MAX_CAPACITY)
     }
 
+    /// Number of appends to a transfer store between syncs to disk.
+    pub fn transfer_sync_batch(&self) -> usize {
+        self.transfer_sync_batch.unwrap_or(1)
+    }
+
     /// Root directory for `ChunkStore`s and cached state. If not set, it defaults to
     /// `DEFAULT_ROOT_DIR_NAME` within the project's data directory (see `Config::root_dir` for the
     /// directories on each platform).
@@ -428,7 +442,7 @@ fn smoke() {
     // NOTE: IF this value is being changed due to a change in the config,
     // the change in config also be handled in Config::merge()
     // and in examples/config_handling.rs
-    let expected_size = 504;
+    let expected_size = 520;
 
     assert_eq!(std::mem::size_of::<Config>(), expected_size);
 }
@@ -110,6 +110,22 @@ pub enum Error {
     /// Transfer has already been registered
     #[error("Transfer has already been registered")]
     TransferAlreadyRegistered,
+    /// No credit with the id was found for the wallet.
+    #[error("No credit found with id {0:?}")]
+    NoSuchCredit(sn_data_types::CreditId),
+    /// Event conflicts with an event already in the transfer store.
+    #[error("Event conflicts with a stored event: {0}")]
+    ConflictingEvent(String),
+    /// A sum of tokens exceeds the representable amount.
+    #[error("Token amount overflow")]
+    TokenOverflow,
+    /// Replica info is inconsistent, e.g. the key index does not match our key share.
+    #[error("Invalid replica info: {0}")]
+    InvalidReplicaInfo(String),
+    /// A transfer store could not be loaded, its events failed verification,
+    /// or a failed write to it could not be undone.
+    #[error("Transfer store is corrupt: {0}")]
+    CorruptStore(String),
     /// Transfer message is invalid.
     #[error("Signed transfer for Dot: '{0:?}' is not valid. Debit or credit are missing")]
     InvalidSignedTransfer(crdts::Dot<PublicKey>),
@@ -44,7 +44,7 @@ impl Node {
                     self.update_replicas().await?;
                     let msg_id =
                         MessageId::combine(vec![our_prefix.name(), XorName::from(our_key)]);
-                    Ok(vec![self.push_state(our_prefix, msg_id)])
+                    Ok(vec![self.push_state(our_prefix, msg_id).await])
                 }
             }
             NodeDuty::SectionSplit {
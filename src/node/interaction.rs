@@ -107,11 +107,11 @@ impl Node {
     }
 
     /// Push our state to the given dst
-    pub fn push_state(&self, prefix: Prefix, msg_id: MessageId) -> NodeDuty {
+    pub async fn push_state(&self, prefix: Prefix, msg_id: MessageId) -> NodeDuty {
         let dst = DstLocation::Section(prefix.name());
 
         let user_wallets = if let Ok(elder) = &self.role.as_elder() {
-            elder.transfers.user_wallets()
+            elder.transfers.user_wallets().await
         } else {
             BTreeMap::new()
         };
@@ -30,7 +30,7 @@ impl Node {
     pub async fn update_replicas(&mut self) -> Result<()> {
         let elder = self.role.as_elder_mut()?;
         let info = replica_info(&self.network_api).await?;
-        elder.transfers.update_replica_info(info);
+        elder.transfers.update_replica_info(info)?;
         Ok(())
     }
 
@@ -49,6 +49,8 @@ pub struct NodeInfo {
     pub node_id: Ed25519PublicKey,
     /// The key used by the node to receive earned rewards.
     pub reward_key: PublicKey,
+    /// Number of appends to a transfer store between syncs to disk.
+    pub transfer_sync_batch: usize,
 }
 
 impl NodeInfo {
@@ -153,6 +155,7 @@ impl Node {
             node_name: network_api.our_name().await,
             node_id: network_api.public_key().await,
             reward_key,
+            transfer_sync_batch: config.transfer_sync_batch(),
         };
 
         let used_space = UsedSpace::new(config.max_capacity());
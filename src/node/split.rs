@@ -75,7 +75,7 @@ impl Node {
         let elder = self.role.as_elder_mut()?;
 
         let info = replica_info(&self.network_api).await?;
-        elder.transfers.update_replica_info(info);
+        elder.transfers.update_replica_info(info)?;
 
         let (wallets, payments) = match &mut elder.section_funds {
             SectionFunds::KeepingNodeWallets { wallets, payments }
@@ -130,10 +130,10 @@ impl Node {
         }
 
         let msg_id = MessageId::combine(vec![our_prefix.name(), XorName::from(our_key)]);
-        ops.push(self.push_state(our_prefix, msg_id));
+        ops.push(self.push_state(our_prefix, msg_id).await);
 
         let msg_id = MessageId::combine(vec![sibling_prefix.name(), XorName::from(sibling_key)]);
-        ops.push(self.push_state(sibling_prefix, msg_id));
+        ops.push(self.push_state(sibling_prefix, msg_id).await);
 
         Ok(ops)
     }
@@ -6,10 +6,19 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use super::{replica_signing::ReplicaSigningImpl, replicas::Replicas, ReplicaInfo};
+use super::{
+    replica_signing::ReplicaSigningImpl, replicas::Replicas, store::DurabilityMode, ReplicaInfo,
+};
 use crate::{network::Network, node::NodeInfo, Error, Result};
+use log::debug;
 use sn_data_types::{ActorHistory, PublicKey};
-use std::collections::BTreeMap;
+use std::{collections::BTreeMap, time::Duration};
+
+/// Number of times the replica info is read, when our keys
+/// change while it is being read, e.g. during churn.
+const REPLICA_INFO_ATTEMPTS: usize = 3;
+/// Time to wait before reading the replica info again.
+const REPLICA_INFO_RETRY_INTERVAL: Duration = Duration::from_millis(100);
 
 pub async fn transfer_replicas(
     node_info: &NodeInfo,
@@ -18,10 +27,34 @@ pub async fn transfer_replicas(
 ) -> Result<Replicas<ReplicaSigningImpl>> {
     let root_dir = node_info.root_dir.clone();
     let info = replica_info(network).await?;
-    Replicas::new(root_dir, info, user_wallets).await
+    let mut replicas = Replicas::new(root_dir, info, user_wallets).await?;
+    let durability = match node_info.transfer_sync_batch {
+        0 | 1 => DurabilityMode::Always,
+        n => DurabilityMode::Batched(n),
+    };
+    replicas.set_durability(durability).await;
+    Ok(replicas)
 }
 
+/// The replica info of our section. Our key share, key index, key set and
+/// section chain are read one at a time, so the info is read again if our
+/// keys changed in between.
 pub async fn replica_info(network: &Network) -> Result<ReplicaInfo<ReplicaSigningImpl>> {
+    let mut attempt = 1;
+    loop {
+        let info = read_replica_info(network).await?;
+        match info.validate() {
+            Err(error) if attempt < REPLICA_INFO_ATTEMPTS => {
+                debug!("Replica info changed while being read: {}", error);
+                attempt += 1;
+                tokio::time::sleep(REPLICA_INFO_RETRY_INTERVAL).await;
+            }
+            result => return result.map(|_| info),
+        }
+    }
+}
+
+async fn read_replica_info(network: &Network) -> Result<ReplicaInfo<ReplicaSigningImpl>> {
     let id = network
         .our_public_key_share()
         .await?
@@ -95,8 +95,8 @@ impl Transfers {
     }
 
     ///
-    pub fn user_wallets(&self) -> BTreeMap<PublicKey, ActorHistory> {
-        self.replicas.user_wallets()
+    pub async fn user_wallets(&self) -> BTreeMap<PublicKey, ActorHistory> {
+        self.replicas.user_wallets().await
     }
 
     pub async fn merge(&mut self, user_wallets: BTreeMap<PublicKey, ActorHistory>) -> Result<()> {
@@ -107,10 +107,11 @@ impl Transfers {
     /// also split the responsibility of the accounts.
     /// Thus, both Replica groups need to drop the accounts that
     /// the other group is now responsible for.
+    /// Returns the number of accounts retained.
     #[allow(unused)]
-    pub async fn split_section(&self, prefix: Prefix) -> Result<()> {
+    pub async fn split_section(&mut self, prefix: Prefix) -> Result<usize> {
         // Removes keys that are no longer our section responsibility.
-        self.replicas.keep_keys_of(prefix).await
+        self.replicas.handle_section_split(prefix).await
     }
 
     ///
@@ -150,8 +151,8 @@ impl Transfers {
     }
 
     ///
-    pub fn update_replica_info(&mut self, info: ReplicaInfo<ReplicaSigningImpl>) {
-        self.replicas.update_replica_info(info);
+    pub fn update_replica_info(&mut self, info: ReplicaInfo<ReplicaSigningImpl>) -> Result<()> {
+        self.replicas.update_replica_info(info)
     }
 
     /// Makes sure the payment contained
@@ -279,8 +280,7 @@ impl Transfers {
     }
 
     fn section_wallet_id(&self) -> PublicKey {
-        let set = self.replicas.replicas_pk_set();
-        PublicKey::Bls(set.public_key())
+        self.replicas.crediting_key()
     }
 
     /// Get all the events of the Replica.
@@ -346,6 +346,7 @@ impl Transfers {
         let result = self
             .replicas
             .history(*wallet_id)
+            .await
             .map_err(|_e| ErrorMessage::NoHistoryForPublicKey(*wallet_id));
 
         Ok(NodeDuty::Send(OutgoingMsg {
@@ -6,30 +6,89 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use super::{replica_signing::ReplicaSigning, store::TransferStore};
-use crate::{Error, Result};
+use super::{
+    replica_signing::ReplicaSigning,
+    store::{DurabilityMode, EventId, StoreEvent, TransferStore},
+};
+use crate::{utils, Error, Result};
 use bls::PublicKeySet;
 use dashmap::DashMap;
 use futures::lock::Mutex;
-use log::info;
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
 use sn_data_types::{
-    ActorHistory, CreditAgreementProof, OwnerType, PublicKey, ReplicaEvent, SignedTransfer, Token,
-    TransferAgreementProof, TransferPropagated, TransferRegistered, TransferValidated,
+    ActorHistory, CreditAgreementProof, CreditId, OwnerType, PublicKey, ReplicaEvent, Signature,
+    SignedTransfer, Token, TransferAgreementProof, TransferPropagated, TransferRegistered,
+    TransferValidated,
 };
+use sn_routing::SectionChain;
 use sn_transfers::WalletReplica;
-use std::{collections::BTreeMap, path::PathBuf, sync::Arc};
-use xor_name::Prefix;
+use std::{
+    collections::{BTreeMap, HashSet, VecDeque},
+    fmt::Debug,
+    io::{Read, Write},
+    path::PathBuf,
+    sync::Arc,
+};
+use xor_name::{Prefix, XorName};
 
 #[cfg(feature = "simulated-payouts")]
 use {
     crate::node_ops::NodeDuty,
     bls::{SecretKey, SecretKeySet},
-    log::debug,
     rand::thread_rng,
-    sn_data_types::{Signature, SignedCredit, SignedDebit, Transfer},
+    sn_data_types::{SignedCredit, Transfer},
 };
 
 type WalletLocks = DashMap<PublicKey, Arc<Mutex<TransferStore<ReplicaEvent>>>>;
+
+/// Version of the snapshot format written by `export_snapshot`.
+const SNAPSHOT_VERSION: u16 = 1;
+
+/// The events of all wallets of the replicas.
+#[derive(Serialize, Deserialize)]
+struct Snapshot {
+    version: u16,
+    wallets: BTreeMap<PublicKey, Vec<ReplicaEvent>>,
+}
+
+/// Number of recently propagated credits remembered,
+/// so that replayed proofs do not cause the wallet to be loaded again.
+const RECENT_CREDITS_CAPACITY: usize = 1024;
+
+/// Default number of wallet stores loaded at a time,
+/// when querying all wallets.
+const DEFAULT_LOAD_CONCURRENCY: usize = 16;
+
+/// Ids of credits recently propagated to our wallets,
+/// evicting the least recently used first.
+#[derive(Default)]
+struct RecentCredits {
+    order: VecDeque<CreditId>,
+    ids: HashSet<CreditId>,
+}
+
+impl RecentCredits {
+    fn contains(&self, id: &CreditId) -> bool {
+        self.ids.contains(id)
+    }
+
+    /// Remembers the id as the most recently used.
+    fn insert(&mut self, id: CreditId) {
+        if !self.ids.insert(id) {
+            // already remembered, so only its recency is refreshed
+            if let Some(position) = self.order.iter().position(|used| *used == id) {
+                let _ = self.order.remove(position);
+            }
+        }
+        self.order.push_back(id);
+        if self.order.len() > RECENT_CREDITS_CAPACITY {
+            if let Some(oldest) = self.order.pop_front() {
+                let _ = self.ids.remove(&oldest);
+            }
+        }
+    }
+}
 ///
 #[derive(Clone, Debug)]
 pub struct ReplicaInfo<T>
@@ -39,19 +98,136 @@ where
     pub id: bls::PublicKeyShare,
     pub key_index: usize,
     pub peer_replicas: PublicKeySet,
-    pub section_chain: sn_routing::SectionChain,
+    pub section_chain: SectionChain,
     pub signing: T,
 }
 
+impl<T: ReplicaSigning> ReplicaInfo<T> {
+    /// Checks that our key share is the one at our key index,
+    /// and that the section chain ends with the key of our peer replicas.
+    pub(crate) fn validate(&self) -> Result<()> {
+        if self.peer_replicas.public_key_share(self.key_index) != self.id {
+            return Err(Error::InvalidReplicaInfo(format!(
+                "Key share does not match the share at index {}.",
+                self.key_index
+            )));
+        }
+        if *self.section_chain.last_key() != self.peer_replicas.public_key() {
+            return Err(Error::InvalidReplicaInfo(
+                "Section chain does not end with the key of the replicas.".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Outcome of a self check of the replicas.
+#[derive(Debug, Default)]
+pub struct HealthReport {
+    /// Status of each tracked wallet.
+    pub wallets: BTreeMap<PublicKey, WalletHealth>,
+}
+
+impl HealthReport {
+    /// Whether no anomalies were detected.
+    #[allow(unused)]
+    pub fn is_healthy(&self) -> bool {
+        self.wallets.values().all(|h| *h == WalletHealth::Healthy)
+    }
+}
+
+/// What to do with wallets that are pruned.
+#[allow(unused)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PrunePolicy {
+    /// Stop tracking the wallet, but keep its store.
+    Untrack,
+    /// Stop tracking the wallet, and delete its store.
+    Delete,
+}
+
+/// Overview of a wallet, read from a single load of its store.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WalletSummary {
+    /// Balance of the wallet.
+    pub balance: Token,
+    /// Number of events of the wallet.
+    pub event_count: usize,
+    /// Kind of the most recent event, if any.
+    pub last_event_kind: Option<EventKind>,
+}
+
+/// Kind of a replica event.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EventKind {
+    /// A transfer was validated.
+    Validated,
+    /// A transfer was registered.
+    Registered,
+    /// A credit was propagated.
+    Propagated,
+    /// Any other event.
+    Other,
+}
+
+impl From<&ReplicaEvent> for EventKind {
+    fn from(event: &ReplicaEvent) -> Self {
+        match event {
+            ReplicaEvent::TransferValidated(_) => Self::Validated,
+            ReplicaEvent::TransferRegistered(_) => Self::Registered,
+            ReplicaEvent::TransferPropagated(_) => Self::Propagated,
+            _ => Self::Other,
+        }
+    }
+}
+
+/// Outcome of an audit of a wallet's history.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct AuditReport {
+    /// Number of events in the history.
+    pub events: usize,
+    /// Position of the first event that would not be accepted
+    /// given its predecessors, and the reason why, if any.
+    pub first_inconsistency: Option<(usize, String)>,
+}
+
+impl AuditReport {
+    /// Whether every event would be accepted.
+    #[allow(unused)]
+    pub fn is_consistent(&self) -> bool {
+        self.first_inconsistency.is_none()
+    }
+}
+
+/// Status of a single wallet in a `HealthReport`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum WalletHealth {
+    /// The store is loadable and all its events verify.
+    Healthy,
+    /// The wallet is tracked, but has no store on disk.
+    MissingStore,
+    /// The store could not be loaded or failed verification.
+    Corrupt(String),
+    /// The same credit is stored more than once.
+    DuplicateCredits(usize),
+}
+
 #[derive(Clone)]
 pub struct Replicas<T>
 where
     T: ReplicaSigning,
 {
     root_dir: PathBuf,
+    /// Whether wallet stores are kept in memory only.
+    in_memory: bool,
     info: ReplicaInfo<T>,
     locks: WalletLocks,
     self_lock: Arc<Mutex<usize>>,
+    recent_credits: Arc<Mutex<RecentCredits>>,
+    /// Max number of wallet stores loaded at a time.
+    load_concurrency: usize,
+    /// How durably the wallet stores persist appended events.
+    durability: DurabilityMode,
 }
 
 impl<T: ReplicaSigning> Replicas<T> {
@@ -60,18 +236,42 @@ impl<T: ReplicaSigning> Replicas<T> {
         info: ReplicaInfo<T>,
         user_wallets: BTreeMap<PublicKey, ActorHistory>,
     ) -> Result<Self> {
+        Self::init(root_dir, false, info, user_wallets).await
+    }
+
+    /// Replicas that keep all wallet stores in memory,
+    /// e.g. for tests and ephemeral nodes.
+    #[allow(unused)]
+    pub(crate) async fn in_memory(
+        info: ReplicaInfo<T>,
+        user_wallets: BTreeMap<PublicKey, ActorHistory>,
+    ) -> Result<Self> {
+        Self::init(PathBuf::new(), true, info, user_wallets).await
+    }
+
+    async fn init(
+        root_dir: PathBuf,
+        in_memory: bool,
+        info: ReplicaInfo<T>,
+        user_wallets: BTreeMap<PublicKey, ActorHistory>,
+    ) -> Result<Self> {
+        info.validate()?;
         let instance = Self {
             root_dir,
+            in_memory,
             info,
             locks: DashMap::new(),
             self_lock: Arc::new(Mutex::new(0)),
+            recent_credits: Arc::default(),
+            load_concurrency: DEFAULT_LOAD_CONCURRENCY,
+            durability: DurabilityMode::default(),
         };
         instance.setup(user_wallets).await?;
         Ok(instance)
     }
 
     pub async fn merge(&mut self, user_wallets: BTreeMap<PublicKey, ActorHistory>) -> Result<()> {
-        self.setup(user_wallets).await // TODO: fix this!!!! (this duplciates entries in db)
+        self.setup(user_wallets).await
     }
 
     async fn setup(&self, user_wallets: BTreeMap<PublicKey, ActorHistory>) -> Result<()> {
@@ -81,6 +281,12 @@ impl<T: ReplicaSigning> Replicas<T> {
         }
         // TODO: parallel
         for (node, wallet) in user_wallets {
+            debug!(
+                "Replica: setting up wallet {} ({} credits, {} debits)",
+                node,
+                wallet.credits.len(),
+                wallet.debits.len()
+            );
             let valid_owners = wallet.credits.iter().all(|c| node == c.recipient())
                 && wallet.debits.iter().all(|d| node == d.sender());
             if !valid_owners {
@@ -88,23 +294,21 @@ impl<T: ReplicaSigning> Replicas<T> {
                     "ActorHistory must contain only transfers of a single actor.".to_string(),
                 ));
             }
-            for credit_proof in wallet.credits {
-                let id = credit_proof.recipient();
-                let e = TransferPropagated(sn_data_types::TransferPropagated { credit_proof });
-                // Acquire lock of the wallet.
-                let key_lock = self.get_load_or_create_store(id).await?;
-                let mut store = key_lock.lock().await;
-                // Access to the specific wallet is now serialised!
-                store.try_insert(e.to_owned())?;
-            }
-            for transfer_proof in wallet.debits {
-                let id = transfer_proof.sender();
-                let e = TransferRegistered(sn_data_types::TransferRegistered { transfer_proof });
-                // Acquire lock of the wallet.
-                let key_lock = self.get_load_or_create_store(id).await?;
-                let mut store = key_lock.lock().await;
-                // Access to the specific wallet is now serialised!
-                store.try_insert(e.to_owned())?;
+            let credits = wallet.credits.into_iter().map(|credit_proof| {
+                TransferPropagated(sn_data_types::TransferPropagated { credit_proof })
+            });
+            let debits = wallet.debits.into_iter().map(|transfer_proof| {
+                TransferRegistered(sn_data_types::TransferRegistered { transfer_proof })
+            });
+            let events: Vec<_> = credits.chain(debits).collect();
+            // histories come from other nodes, so nothing is stored unless all of it verifies
+            self.verify_history(node, &events)?;
+            // Acquire lock of the wallet.
+            let key_lock = self.get_load_or_create_store(node).await?;
+            let mut store = key_lock.lock().await;
+            // Access to the specific wallet is now serialised!
+            for e in events {
+                insert_unless_stored(&mut store, e)?;
             }
         }
         Ok(())
@@ -116,60 +320,81 @@ impl<T: ReplicaSigning> Replicas<T> {
 
     /// The total amount in wallets managed
     /// by the replicas in this section.
+    /// Wallets that cannot be loaded are skipped, with a warning.
     pub async fn managed_amount(&self) -> Result<Token> {
-        let mut amount = 0;
-        for entry in &self.locks {
-            let key = *entry.key();
-            amount += self.balance(key).await?.as_nano();
+        let mut amount = Token::zero();
+        let results = self
+            .query_all_stores({
+                let section_chain = self.info.section_chain.clone();
+                move |store| verified_events(&section_chain, store)
+            })
+            .await;
+        for (id, result) in results {
+            let wallet = match result {
+                Ok(Some(events)) => {
+                    events.and_then(|events| self.wallet_from_events(OwnerType::Single(id), events))
+                }
+                // no store, so it holds nothing
+                Ok(None) => continue,
+                Err(error) => Err(error),
+            };
+            let wallet = match wallet {
+                Ok(wallet) => wallet,
+                Err(error) => {
+                    warn!("Replica: skipping managed amount of {}: {}", id, error);
+                    continue;
+                }
+            };
+            amount = amount
+                .checked_add(wallet.balance())
+                .ok_or(Error::TokenOverflow)?;
         }
-        Ok(Token::from_nano(amount))
+        Ok(amount)
     }
 
-    ///
-    pub fn user_wallets(&self) -> BTreeMap<PublicKey, ActorHistory> {
-        let wallets = self
-            .locks
-            .iter()
-            .map(|r| *r.key())
-            .filter_map(|id| self.history(id).ok().map(|history| Some((id, history))))
-            .flatten()
-            .collect();
+    /// The managed amount, formatted for display.
+    #[allow(unused)]
+    pub async fn managed_amount_display(&self) -> Result<String> {
+        Ok(self.managed_amount().await?.to_string())
+    }
+
+    /// Histories of all tracked wallets. Wallets whose
+    /// store cannot be read are skipped, with a warning.
+    pub async fn user_wallets(&self) -> BTreeMap<PublicKey, ActorHistory> {
+        let mut wallets = BTreeMap::new();
+        for id in self.wallet_ids() {
+            match self.history(id).await {
+                Ok(history) => {
+                    let _ = wallets.insert(id, history);
+                }
+                Err(error) => warn!("Replica: skipping history of {}: {}", id, error),
+            }
+        }
         wallets
     }
 
-    /// All keys' histories
+    /// All keys' verified histories, ordered by wallet id,
+    /// and then by the order of the events in the wallet's store.
+    /// Wallets whose events fail verification are skipped, with a warning.
     pub async fn all_events(&self) -> Result<Vec<ReplicaEvent>> {
-        let events = self
-            .locks
-            .iter()
-            .map(|r| *r.key())
-            .filter_map(|id| TransferStore::new(id.into(), &self.root_dir).ok())
-            .map(|store| store.get_all())
-            .flatten()
-            .collect();
+        let section_chain = self.info.section_chain.clone();
+        let results = self
+            .query_all_stores(move |store| verified_events(&section_chain, store))
+            .await;
+        let mut events = vec![];
+        for (id, result) in results {
+            match result.and_then(|events| events.transpose()) {
+                Ok(wallet_events) => events.extend(wallet_events.unwrap_or_default()),
+                Err(error) => warn!("Replica: skipping events of {}: {}", id, error),
+            }
+        }
         Ok(events)
     }
 
-    /// History of actor
-    pub fn history(&self, id: PublicKey) -> Result<ActorHistory> {
-        let store = TransferStore::new(id.into(), &self.root_dir);
-
-        if let Err(error) = store {
-            // hmm.. can we handle this in a better way?
-            let err_string = error.to_string();
-            let no_such_file_or_dir = err_string.contains("No such file or directory");
-            let system_cannot_find_file =
-                err_string.contains("The system cannot find the file specified");
-            if no_such_file_or_dir || system_cannot_find_file {
-                // we have no history yet, so lets report that.
-                return Ok(ActorHistory::empty());
-            }
-
-            return Err(error);
-        };
-
-        let store = store?;
-        let events = store.get_all();
+    /// History of actor, once its events are verified. Unknown wallets
+    /// have an empty history, and querying them does not create a store.
+    pub async fn history(&self, id: PublicKey) -> Result<ActorHistory> {
+        let events = self.query_verified_events(id).await?.unwrap_or_default();
 
         if events.is_empty() {
             return Ok(ActorHistory::empty());
@@ -183,6 +408,15 @@ impl<T: ReplicaSigning> Replicas<T> {
         Ok(history)
     }
 
+    /// The events of the wallet, once their signatures are verified,
+    /// e.g. for a client to sync its own wallet. Unknown wallets have
+    /// no events, and querying them does not create a store.
+    #[allow(unused)]
+    pub async fn export_wallet(&self, id: PublicKey) -> Result<Vec<ReplicaEvent>> {
+        let events = self.query_verified_events(id).await?;
+        Ok(events.unwrap_or_default())
+    }
+
     fn get_credits(&self, events: &[ReplicaEvent]) -> Vec<CreditAgreementProof> {
         use itertools::Itertools;
         events
@@ -212,290 +446,3095 @@ impl<T: ReplicaSigning> Replicas<T> {
         debits
     }
 
-    ///
+    /// Balance of a wallet. Unknown wallets have a zero balance,
+    /// and querying them does not create a store.
     pub async fn balance(&self, id: PublicKey) -> Result<Token> {
         debug!("Replica: Getting balance of: {:?}", id);
-        let store = match TransferStore::new(id.into(), &self.root_dir) {
-            Ok(store) => store,
-            // store load failed, so we return 0 balance
-            Err(_) => return Ok(Token::from_nano(0)),
+        let events = match self
+            .query_store(id, {
+                let section_chain = self.info.section_chain.clone();
+                move |store| verified_events(&section_chain, store)
+            })
+            .await
+        {
+            Ok(Some(events)) => events?,
+            // no store, so we return 0 balance
+            Ok(None) => return Ok(Token::from_nano(0)),
+            Err(error) => return Err(error),
         };
-
-        let wallet = self.load_wallet(&store, OwnerType::Single(id)).await?;
+        let wallet = self.wallet_from_events(OwnerType::Single(id), events)?;
         Ok(wallet.balance())
     }
 
-    /// Get the replica's PK set
-    pub fn replicas_pk_set(&self) -> PublicKeySet {
-        self.info.peer_replicas.clone()
+    /// Balance, event count and last activity of a wallet,
+    /// from a single load of its store.
+    #[allow(unused)]
+    pub async fn wallet_summary(&self, id: PublicKey) -> Result<WalletSummary> {
+        let events = self.query_verified_events(id).await?.unwrap_or_default();
+        let event_count = events.len();
+        let last_event_kind = events.last().map(EventKind::from);
+        let wallet = self.wallet_from_events(OwnerType::Single(id), events)?;
+        Ok(WalletSummary {
+            balance: wallet.balance(),
+            event_count,
+            last_event_kind,
+        })
     }
 
-    /// -----------------------------------------------------------------
-    /// ---------------------- Cmds -------------------------------------
-    /// -----------------------------------------------------------------
-
-    ///
-    pub fn update_replica_info(&mut self, info: ReplicaInfo<T>) {
-        self.info = info;
+    /// Balance of a wallet, with the proof of the most recent
+    /// credit propagated to it, so that clients can cross-check it.
+    #[allow(unused)]
+    pub async fn balance_and_proof(
+        &self,
+        id: PublicKey,
+    ) -> Result<(Token, Option<CreditAgreementProof>)> {
+        let events = self.query_verified_events(id).await?.unwrap_or_default();
+        let latest_credit = events.iter().rev().find_map(|e| match e {
+            ReplicaEvent::TransferPropagated(e) => Some(e.credit_proof.clone()),
+            _ => None,
+        });
+        let wallet = self.wallet_from_events(OwnerType::Single(id), events)?;
+        Ok((wallet.balance(), latest_credit))
     }
 
+    /// Balances of all tracked wallets, read in a single pass.
+    /// Wallets that cannot be loaded are skipped, with a warning.
     #[allow(unused)]
-    pub async fn keep_keys_of(&self, prefix: Prefix) -> Result<()> {
-        // Removes keys that are no longer our section responsibility.
-        let keys: Vec<PublicKey> = self.locks.iter().map(|r| *r.key()).collect();
-        for key in keys.into_iter() {
-            if !prefix.matches(&key.into()) {
-                let key_lock = self.load_key_lock(key).await?;
-                let _store = key_lock.lock().await;
-                let _ = self.locks.remove(&key);
-                // todo: remove db from disk
+    pub async fn all_balances(&self) -> Result<BTreeMap<PublicKey, Token>> {
+        let mut balances = BTreeMap::new();
+        for id in self.wallet_ids() {
+            let wallet = self.query_verified_events(id).await.and_then(|events| {
+                self.wallet_from_events(OwnerType::Single(id), events.unwrap_or_default())
+            });
+            match wallet {
+                Ok(wallet) => {
+                    let _ = balances.insert(id, wallet.balance());
+                }
+                Err(error) => warn!("Replica: skipping balance of {}: {}", id, error),
             }
         }
-        Ok(())
+        Ok(balances)
     }
 
-    /// Step 1. Main business logic validation of a debit.
-    pub async fn validate(&self, signed_transfer: SignedTransfer) -> Result<TransferValidated> {
-        debug!("Replica validating transfer: {:?}", signed_transfer);
-        let id = signed_transfer.sender();
-        // Acquire lock of the wallet.
-        let key_lock = self.load_key_lock(id).await?;
-        let mut store = key_lock.lock().await;
-
-        // Access to the specific wallet is now serialised!
-        let wallet = self.load_wallet(&store, OwnerType::Single(id)).await?;
-
-        debug!("Wallet loaded");
-        let _ = wallet.validate(&signed_transfer.debit, &signed_transfer.credit)?;
-
-        debug!("wallet valid");
-        // signing will be serialised
-        let (replica_debit_sig, replica_credit_sig) =
-            self.info.signing.sign_transfer(&signed_transfer).await?;
-        // release lock and update state
-        let event = TransferValidated {
-            signed_credit: signed_transfer.credit,
-            signed_debit: signed_transfer.debit,
-            replica_debit_sig,
-            replica_credit_sig,
-            replicas: self.info.peer_replicas.clone(),
-        };
-
-        // first store to disk
-        store.try_insert(ReplicaEvent::TransferValidated(event.clone()))?;
-        let mut wallet = wallet;
-        // then apply to inmem state
-        wallet.apply(ReplicaEvent::TransferValidated(event.clone()))?;
+    /// Number of events of each tracked wallet, so that a syncing
+    /// peer can request only the events it is missing.
+    #[allow(unused)]
+    pub async fn latest_events(&self) -> Result<BTreeMap<PublicKey, usize>> {
+        let mut counts = BTreeMap::new();
+        for id in self.wallet_ids() {
+            let count = self
+                .query_store(id, |store| store.len())
+                .await?
+                .unwrap_or_default();
+            let _ = counts.insert(id, count);
+        }
+        Ok(counts)
+    }
 
-        Ok(event)
+    /// The verified events of a wallet after its first `offset` events,
+    /// so that a lagging replica can catch up on only what it is missing.
+    #[allow(unused)]
+    pub async fn events_since(&self, id: PublicKey, offset: usize) -> Result<Vec<ReplicaEvent>> {
+        let events = self.query_verified_events(id).await?.unwrap_or_default();
+        if offset > events.len() {
+            return Err(Error::InvalidOperation(format!(
+                "Wallet has only {} events, offset {} was requested.",
+                events.len(),
+                offset
+            )));
+        }
+        Ok(events.into_iter().skip(offset).collect())
     }
 
-    /// Step 2. Validation of agreement, and order at debit source.
-    pub async fn register(
+    /// The proof of a credit that was propagated to the wallet.
+    #[allow(unused)]
+    pub async fn credit_proof_for(
         &self,
-        transfer_proof: &TransferAgreementProof,
-    ) -> Result<TransferRegistered> {
-        let id = transfer_proof.sender();
+        id: PublicKey,
+        credit_id: CreditId,
+    ) -> Result<CreditAgreementProof> {
+        let event = self
+            .query_store(id, move |store| {
+                store.get_by_id(&EventId::Propagated(credit_id))
+            })
+            .await?
+            .flatten();
+        match event {
+            Some(ReplicaEvent::TransferPropagated(e)) => Ok(e.credit_proof),
+            _ => Err(Error::NoSuchCredit(credit_id)),
+        }
+    }
 
-        // should only have been signed by our section
-        let known_key = self.exists_in_chain(&transfer_proof.replica_keys().public_key());
-        if !known_key {
-            return Err(Error::Transfer(sn_transfers::Error::SectionKeyNeverExisted));
+    /// Balance of a wallet after only its first `up_to_event` events.
+    #[allow(unused)]
+    pub async fn balance_at(&self, id: PublicKey, up_to_event: usize) -> Result<Token> {
+        let mut events = self.query_verified_events(id).await?.unwrap_or_default();
+        if up_to_event > events.len() {
+            return Err(Error::InvalidOperation(format!(
+                "Wallet has only {} events, {} were requested.",
+                events.len(),
+                up_to_event
+            )));
         }
+        events.truncate(up_to_event);
+        let wallet = self.wallet_from_events(OwnerType::Single(id), events)?;
+        Ok(wallet.balance())
+    }
 
-        // Acquire lock of the wallet.
+    /// Balance of a wallet, formatted for display.
+    #[allow(unused)]
+    pub async fn balance_display(&self, id: PublicKey) -> Result<String> {
+        Ok(self.balance(id).await?.to_string())
+    }
+
+    /// Checks whether a transfer would pass validation,
+    /// without signing it or writing anything to the store.
+    #[allow(unused)]
+    pub async fn can_validate(&self, signed_transfer: &SignedTransfer) -> Result<()> {
+        let id = signed_transfer.sender();
+        // Acquire lock of the wallet, so that only committed state is seen.
         let key_lock = self.load_key_lock(id).await?;
         let mut store = key_lock.lock().await;
 
-        // Access to the specific wallet is now serialised!
-        let wallet = self.load_wallet(&store, OwnerType::Single(id)).await?;
-        match wallet.register(transfer_proof)? {
-            None => {
-                info!("transfer already registered!");
-                Err(Error::TransferAlreadyRegistered)
-            }
-            Some(event) => {
-                // first store to disk
-                store.try_insert(ReplicaEvent::TransferRegistered(event.clone()))?;
-                let mut wallet = wallet;
-                // then apply to inmem state
-                wallet.apply(ReplicaEvent::TransferRegistered(event.clone()))?;
-                Ok(event)
-            }
-        }
+        let wallet = self.load_wallet(&mut store, OwnerType::Single(id)).await?;
+        let _ = wallet.validate(&signed_transfer.debit, &signed_transfer.credit)?;
+        Ok(())
     }
 
-    /// Step 3. Validation of DebitAgreementProof, and credit idempotency at credit destination.
-    /// (Since this leads to a credit, there is no requirement on order.)
-    pub async fn receive_propagated(
-        &self,
-        _debiting_replicas_name: xor_name::XorName,
-        credit_proof: &CreditAgreementProof,
-    ) -> Result<TransferPropagated> {
-        // Acquire lock of the wallet.
-        let id = credit_proof.recipient();
-        let _debiting_replicas_key = credit_proof.replica_keys().public_key();
-
-        // TODO: check the debiting_replicas_key, needs reverse AE implemented
-
-        // Only when propagated is there a risk that the store doesn't exist,
-        // and that we want to create it. All other write operations require that
-        // a propagation has occurred first. Read ops simply return error when it doesn't exist.
-        let key_lock = match self.load_key_lock(id).await {
-            Ok(key_lock) => key_lock,
-            Err(_) => {
-                // lock on us, but only when store doesn't exist
-                let self_lock = self.self_lock.lock().await;
-                match self.load_key_lock(id).await {
-                    Ok(store) => store,
-                    Err(_) => {
-                        // no key lock (hence no store), so we create one
-                        let store = TransferStore::new(id.into(), &self.root_dir)?;
-                        let locked_store = Arc::new(Mutex::new(store));
-                        let _ = self.locks.insert(id, locked_store.clone());
-                        let _ = self_lock.overflowing_add(0); // resolve: is a usage at end of block necessary to actually engage the lock?
-                        locked_store
-                    }
-                }
-            }
-        };
+    /// Whether the wallet is tracked in memory by these replicas.
+    #[allow(unused)]
+    pub fn knows_wallet(&self, id: &PublicKey) -> bool {
+        self.locks.contains_key(id)
+    }
 
-        let mut store = key_lock.lock().await;
+    /// Whether a store for the wallet exists on disk.
+    /// A wallet can exist on disk without yet being tracked in memory.
+    /// In-memory replicas have no stores on disk.
+    #[allow(unused)]
+    pub async fn store_exists_on_disk(&self, id: &PublicKey) -> bool {
+        if self.in_memory {
+            return false;
+        }
+        TransferStore::<ReplicaEvent>::exists((*id).into(), &self.root_dir).unwrap_or(false)
+    }
 
-        // Access to the specific wallet is now serialised!
-        let wallet = self.load_wallet(&store, OwnerType::Single(id)).await?;
-        let propagation_result = wallet.receive_propagated(credit_proof);
-        if propagation_result.is_ok() {
-            // update state
-            let event = TransferPropagated {
-                credit_proof: credit_proof.clone(),
-            };
-            // only add it locally if we don't know about it... (this prevents SimulatedPayouts being reapplied due to varied sigs.)
-            if propagation_result?.is_some() {
-                // first store to disk
-                store.try_insert(ReplicaEvent::TransferPropagated(event.clone()))?;
-                let mut wallet = wallet;
-                // then apply to inmem state
-                wallet.apply(ReplicaEvent::TransferPropagated(event.clone()))?;
+    /// Read-only diagnostic of the tracked wallets, checking that
+    /// each has a loadable store whose events all verify.
+    #[allow(unused)]
+    pub async fn self_check(&self) -> Result<HealthReport> {
+        let mut report = HealthReport::default();
+        for id in self.wallet_ids() {
+            let health = self.wallet_health(id).await;
+            if health != WalletHealth::Healthy {
+                warn!("Replica: wallet {} is unhealthy: {:?}", id, health);
             }
-            return Ok(event);
+            let _ = report.wallets.insert(id, health);
         }
-        Err(Error::InvalidPropagatedTransfer(credit_proof.clone()))
+        Ok(report)
     }
 
-    async fn load_key_lock(
-        &self,
-        id: PublicKey,
-    ) -> Result<Arc<Mutex<TransferStore<ReplicaEvent>>>> {
-        match self.locks.get(&id) {
-            Some(val) => Ok(val.clone()),
-            None => Err(Error::Logic("Key does not exist among locks.".to_string())),
+    async fn wallet_health(&self, id: PublicKey) -> WalletHealth {
+        use itertools::Itertools;
+        let events = match self.query_verified_events(id).await {
+            Ok(Some(events)) => events,
+            Ok(None) => return WalletHealth::MissingStore,
+            Err(error) => return WalletHealth::Corrupt(error.to_string()),
+        };
+        let credits: Vec<_> = events
+            .iter()
+            .filter_map(|e| match e {
+                ReplicaEvent::TransferPropagated(e) => Some(*e.credit_proof.id()),
+                _ => None,
+            })
+            .collect();
+        let duplicates = credits.len() - credits.iter().unique().count();
+        if duplicates > 0 {
+            return WalletHealth::DuplicateCredits(duplicates);
+        }
+        match self.wallet_from_events(OwnerType::Single(id), events) {
+            Ok(_) => WalletHealth::Healthy,
+            Err(error) => WalletHealth::Corrupt(error.to_string()),
         }
     }
 
-    async fn load_wallet(
-        &self,
-        store: &TransferStore<ReplicaEvent>,
-        id: OwnerType,
-    ) -> Result<WalletReplica> {
-        let events = store.get_all();
-        let wallet = WalletReplica::from_history(
-            id,
-            self.info.id,
-            self.info.key_index,
-            self.info.peer_replicas.clone(),
-            events,
+    /// Replays the history of the wallet, checking that every event
+    /// is validly signed, and would be accepted given its predecessors.
+    /// Unlike loading the wallet, this also checks balances and ordering.
+    #[allow(unused)]
+    pub async fn audit_wallet(&self, id: PublicKey) -> Result<AuditReport> {
+        let events = self
+            .query_store(id, |store| store.get_all())
+            .await?
+            .unwrap_or_default();
+        let mut report = AuditReport {
+            events: events.len(),
+            first_inconsistency: None,
+        };
+        let mut wallet = self.wallet_from_events(OwnerType::Single(id), vec![])?;
+        for (index, event) in events.into_iter().enumerate() {
+            if let Err(error) = self.replay(&mut wallet, id, event) {
+                warn!(
+                    "Replica: audit of wallet {} failed at event {}: {}",
+                    id, index, error
+                );
+                report.first_inconsistency = Some((index, error.to_string()));
+                break;
+            }
+        }
+        Ok(report)
+    }
+
+    /// Applies the event to the wallet, if it would be accepted given the prior events.
+    fn replay(&self, wallet: &mut WalletReplica, id: PublicKey, event: ReplicaEvent) -> Result<()> {
+        if wallet_id_of(&event) != Some(id) {
+            return Err(Error::InvalidOperation(
+                "Event is not of the wallet.".to_string(),
+            ));
+        }
+        if !is_valid_event(&self.info.section_chain, &event)? {
+            return Err(Error::InvalidOperation(
+                "Event is not validly signed.".to_string(),
+            ));
+        }
+        let accepted = match &event {
+            ReplicaEvent::TransferValidated(e) => wallet
+                .validate(&e.signed_debit, &e.signed_credit)
+                .map(|_| true)?,
+            // the proof was verified against its own keys above, which can be those of an
+            // earlier section key, so only its order is checked
+            ReplicaEvent::TransferRegistered(e) => {
+                let proof = &e.transfer_proof;
+                if proof.signed_credit.id() != &proof.signed_debit.credit_id()? {
+                    return Err(Error::InvalidOperation(
+                        "Debit and credit of the proof do not match.".to_string(),
+                    ));
+                }
+                let counter = proof.id().counter;
+                let next_debit = wallet.wallet().map(|w| w.debit_version).unwrap_or_default();
+                if counter != next_debit {
+                    return Err(Error::Transfer(sn_transfers::Error::OperationOutOfOrder(
+                        counter, next_debit,
+                    )));
+                }
+                true
+            }
+            ReplicaEvent::TransferPropagated(e) => {
+                wallet.receive_propagated(&e.credit_proof)?.is_some()
+            }
+            _ => true,
+        };
+        if !accepted {
+            return Err(Error::InvalidOperation(
+                "Event duplicates a prior event.".to_string(),
+            ));
+        }
+        wallet.apply(event)?;
+        Ok(())
+    }
+
+    /// Verifies that a proof is well-formed, and signed by a key
+    /// of our section chain, without touching any store.
+    #[allow(unused)]
+    pub fn verify_transfer_proof(&self, proof: &TransferAgreementProof) -> Result<()> {
+        let keys = &proof.debiting_replicas_keys;
+        if !exists_in_chain(&self.info.section_chain, &keys.public_key()) {
+            return Err(Error::Transfer(sn_transfers::Error::SectionKeyNeverExisted));
+        }
+        let debit = &proof.signed_debit.debit;
+        let credit = &proof.signed_credit.credit;
+        if debit.amount != credit.amount || debit.credit_id()? != credit.id {
+            return Err(Error::InvalidOperation(
+                "Debit and credit of the proof do not match.".to_string(),
+            ));
+        }
+        proof.sender().verify(
+            &proof.signed_debit.actor_signature,
+            utils::serialise(debit)?,
         )?;
-        Ok(wallet)
+        if !verify_sig(keys, &proof.debit_sig, &proof.signed_debit)?
+            || !verify_sig(keys, &proof.credit_sig, &proof.signed_credit)?
+        {
+            return Err(Error::InvalidSignedTransfer(proof.id()));
+        }
+        Ok(())
+    }
+
+    /// Keys of all wallets tracked by these replicas.
+    pub fn wallet_ids(&self) -> Vec<PublicKey> {
+        self.locks.iter().map(|r| *r.key()).collect()
+    }
+
+    /// Get the replica's PK set
+    #[allow(unused)]
+    pub fn replicas_pk_set(&self) -> PublicKeySet {
+        self.info.peer_replicas.clone()
+    }
+
+    /// Index of our key share among the replicas.
+    #[allow(unused)]
+    pub fn key_index(&self) -> usize {
+        self.info.key_index
+    }
+
+    /// Number of replica signatures that must be exceeded to form an agreement proof.
+    #[allow(unused)]
+    pub fn threshold(&self) -> usize {
+        self.info.peer_replicas.threshold()
+    }
+
+    /// Number of replica signatures needed to form an agreement proof.
+    #[allow(unused)]
+    pub fn quorum_size(&self) -> usize {
+        self.threshold() + 1
+    }
+
+    /// The key that credits to the section are made to.
+    /// This is currently the key of our peer replicas.
+    pub fn crediting_key(&self) -> PublicKey {
+        PublicKey::Bls(self.info.peer_replicas.public_key())
+    }
+
+    /// -----------------------------------------------------------------
+    /// ---------------------- Cmds -------------------------------------
+    /// -----------------------------------------------------------------
+
+    /// Sets the max number of wallet stores loaded
+    /// at a time, when querying all wallets.
+    #[allow(unused)]
+    pub fn set_load_concurrency(&mut self, concurrency: usize) {
+        self.load_concurrency = concurrency.max(1);
+    }
+
+    /// Sets how durably the wallet stores persist appended events,
+    /// both of the tracked wallets, and of those tracked later.
+    pub async fn set_durability(&mut self, durability: DurabilityMode) {
+        self.durability = durability;
+        for id in self.wallet_ids() {
+            if let Ok(key_lock) = self.load_key_lock(id).await {
+                key_lock.lock().await.set_durability(durability);
+            }
+        }
+    }
+
+    /// Replaces the replica info, e.g. on churn, once it is checked to be consistent.
+    pub fn update_replica_info(&mut self, info: ReplicaInfo<T>) -> Result<()> {
+        info.validate()?;
+        self.info = info;
+        Ok(())
+    }
+
+    /// Imports an externally provided history of a wallet, e.g. for
+    /// recovery after data loss. The events are verified, and the import
+    /// is refused if it conflicts with the events already stored.
+    #[allow(unused)]
+    pub async fn import_history(&self, id: PublicKey, events: Vec<ReplicaEvent>) -> Result<()> {
+        self.verify_history(id, &events)?;
+
+        // no store is created for, or adopted by, the wallet until we are done
+        let _self_lock = self.self_lock.lock().await;
+        if let Ok(key_lock) = self.load_key_lock(id).await {
+            // Acquire lock of the wallet.
+            let mut store = key_lock.lock().await;
+            // Access to the specific wallet is now serialised!
+            if conflicts_with_stored(&store.get_all(), &events) {
+                return Err(history_conflict());
+            }
+            return extend_history(&mut store, events);
+        }
+
+        let existing = self
+            .query_store(id, |store| store.get_all())
+            .await?
+            .unwrap_or_default();
+        if conflicts_with_stored(&existing, &events) {
+            return Err(history_conflict());
+        }
+        // the store is only created once the import is accepted
+        let existed = self.store_exists_on_disk(&id).await;
+        let mut store = self.create_store(id)?;
+        if let Err(error) = extend_history(&mut store, events) {
+            if !existed && !self.in_memory {
+                // the store is dropped before deletion, as it writes itself to disk when dropped
+                drop(store);
+                TransferStore::<ReplicaEvent>::delete(id.into(), &self.root_dir)?;
+            }
+            return Err(error);
+        }
+        let _ = self.locks.insert(id, Arc::new(Mutex::new(store)));
+        Ok(())
+    }
+
+    /// Writes the verified events of all wallets to the writer, in a versioned format.
+    /// Fails if the events of any wallet fail verification.
+    #[allow(unused)]
+    pub async fn export_snapshot<W: Write>(&self, writer: W) -> Result<()> {
+        let mut wallets = BTreeMap::new();
+        for id in self.wallet_ids() {
+            // Acquire lock of the wallet.
+            let key_lock = self.load_key_lock(id).await?;
+            let mut store = key_lock.lock().await;
+            let events = verified_events(&self.info.section_chain, &mut store)?;
+            let _ = wallets.insert(id, events);
+        }
+        let snapshot = Snapshot {
+            version: SNAPSHOT_VERSION,
+            wallets,
+        };
+        bincode::serialize_into(writer, &snapshot).map_err(Error::Bincode)
+    }
+
+    /// Restores the wallets of a snapshot written by `export_snapshot`.
+    /// All wallets are verified, and checked against the stored events,
+    /// before any of them is imported. Either all wallets are imported,
+    /// or, if an import fails, what was imported is rolled back.
+    /// Returns the number of wallets imported.
+    #[allow(unused)]
+    pub async fn import_snapshot<R: Read>(&self, reader: R) -> Result<usize> {
+        let snapshot: Snapshot = bincode::deserialize_from(reader).map_err(Error::Bincode)?;
+        if snapshot.version != SNAPSHOT_VERSION {
+            return Err(Error::InvalidOperation(format!(
+                "Unsupported snapshot version: {}",
+                snapshot.version
+            )));
+        }
+        for (id, events) in &snapshot.wallets {
+            self.verify_history(*id, events)?;
+        }
+
+        // no store is created for, or adopted by, the untracked wallets until we are done
+        let _self_lock = self.self_lock.lock().await;
+        // Acquire locks of the tracked wallets.
+        let key_locks: BTreeMap<_, _> = snapshot
+            .wallets
+            .keys()
+            .filter_map(|id| self.locks.get(id).map(|key_lock| (*id, key_lock.clone())))
+            .collect();
+        let mut tracked = BTreeMap::new();
+        for (id, key_lock) in &key_locks {
+            let _ = tracked.insert(*id, key_lock.lock().await);
+        }
+
+        // Access to the wallets is now serialised!
+        for (id, events) in &snapshot.wallets {
+            let existing = match tracked.get(id) {
+                Some(store) => store.get_all(),
+                None => self
+                    .query_store(*id, |store| store.get_all())
+                    .await?
+                    .unwrap_or_default(),
+            };
+            if conflicts_with_stored(&existing, events) {
+                return Err(history_conflict());
+            }
+        }
+
+        let count = snapshot.wallets.len();
+        // the number of events each wallet had before the import, so that it can be rolled back
+        let mut imported = vec![];
+        let mut created = vec![];
+        let mut failure = None;
+        for (id, events) in snapshot.wallets {
+            let result = match tracked.get_mut(&id) {
+                Some(store) => {
+                    imported.push((id, store.len()));
+                    extend_history(store, events)
+                }
+                None => {
+                    let existed = self.store_exists_on_disk(&id).await;
+                    self.create_store(id).and_then(|mut store| {
+                        let len = store.len();
+                        let result = extend_history(&mut store, events);
+                        created.push((id, store, len, existed));
+                        result
+                    })
+                }
+            };
+            if let Err(error) = result {
+                failure = Some(error);
+                break;
+            }
+        }
+
+        if let Some(error) = failure {
+            for (id, len) in imported {
+                if let Some(store) = tracked.get_mut(&id) {
+                    if let Err(error) = store.truncate(len) {
+                        warn!("Replica: could not roll back import of {}: {}", id, error);
+                    }
+                }
+            }
+            for (id, mut store, len, existed) in created {
+                let rolled_back = if existed {
+                    store.truncate(len)
+                } else {
+                    // the store is dropped before deletion, as it writes itself to disk when dropped
+                    drop(store);
+                    if self.in_memory {
+                        Ok(())
+                    } else {
+                        TransferStore::<ReplicaEvent>::delete(id.into(), &self.root_dir)
+                    }
+                };
+                if let Err(error) = rolled_back {
+                    warn!("Replica: could not roll back import of {}: {}", id, error);
+                }
+            }
+            return Err(error);
+        }
+        for (id, store, _, _) in created {
+            let _ = self.locks.insert(id, Arc::new(Mutex::new(store)));
+        }
+        info!("Imported {} wallets from snapshot.", count);
+        Ok(count)
+    }
+
+    /// Re-signs transfers that were validated, but not yet registered,
+    /// under a previous replica key set, using our current keys.
+    /// Returns the number of events that were re-signed.
+    #[allow(unused)]
+    pub async fn resign_pending(&self) -> Result<usize> {
+        let mut count = 0;
+        for id in self.wallet_ids() {
+            // Acquire lock of the wallet.
+            let key_lock = self.load_key_lock(id).await?;
+            let mut store = key_lock.lock().await;
+
+            // Access to the specific wallet is now serialised!
+            let events = store.get_all();
+            let registered: HashSet<_> = events
+                .iter()
+                .filter_map(|e| match e {
+                    ReplicaEvent::TransferRegistered(e) => Some(e.id()),
+                    _ => None,
+                })
+                .collect();
+            for (index, event) in events.iter().enumerate() {
+                let validated = match event {
+                    ReplicaEvent::TransferValidated(e) => e,
+                    _ => continue,
+                };
+                if validated.replicas == self.info.peer_replicas
+                    || registered.contains(&validated.signed_debit.id())
+                {
+                    continue;
+                }
+                let signed_transfer = SignedTransfer {
+                    debit: validated.signed_debit.clone(),
+                    credit: validated.signed_credit.clone(),
+                };
+                let (replica_debit_sig, replica_credit_sig) =
+                    self.info.signing.sign_transfer(&signed_transfer).await?;
+                let event = TransferValidated {
+                    signed_credit: signed_transfer.credit,
+                    signed_debit: signed_transfer.debit,
+                    replica_debit_sig,
+                    replica_credit_sig,
+                    replicas: self.info.peer_replicas.clone(),
+                };
+                store.replace(index, ReplicaEvent::TransferValidated(event))?;
+                count += 1;
+            }
+        }
+        info!("Re-signed {} pending transfers.", count);
+        Ok(count)
+    }
+
+    /// Re-syncs the wallet locks with the stores on disk, e.g. after
+    /// an external restore. Stores of tracked wallets are left as they are,
+    /// and stores that cannot be loaded, or whose events fail verification,
+    /// are skipped, with a warning. Only the stores that are added are
+    /// opened for writing. Returns the number of wallets added.
+    #[allow(unused)]
+    pub async fn rebuild_locks_from_disk(&mut self) -> Result<usize> {
+        if self.in_memory {
+            return Ok(0);
+        }
+        let _self_lock = self.self_lock.lock().await;
+        // the stores of tracked wallets are never opened a second time
+        let tracked: HashSet<XorName> = self.wallet_ids().into_iter().map(XorName::from).collect();
+        let mut added = 0;
+        for name in TransferStore::<ReplicaEvent>::list(&self.root_dir)? {
+            if tracked.contains(&name) {
+                continue;
+            }
+            // candidates are only read, so that a rejected store is never written back to disk
+            let events = TransferStore::open_read_only(name, &self.root_dir)
+                .and_then(|mut store| verified_events(&self.info.section_chain, &mut store));
+            let events = match events {
+                Ok(events) => events,
+                Err(error) => {
+                    warn!("Skipping the transfer store of {}: {}", name, error);
+                    continue;
+                }
+            };
+            let id = match events.first().and_then(wallet_id_of) {
+                Some(id) => id,
+                None => continue,
+            };
+            if XorName::from(id) != name {
+                continue;
+            }
+            let mut store = match TransferStore::new(name, &self.root_dir) {
+                Ok(store) => store,
+                Err(error) => {
+                    warn!("Skipping the transfer store of {}: {}", name, error);
+                    continue;
+                }
+            };
+            // no tracked wallet writes to the store, so it holds the events just verified
+            store.set_verified(events.len());
+            store.set_durability(self.durability);
+            let _ = self.locks.insert(id, Arc::new(Mutex::new(store)));
+            added += 1;
+        }
+        info!("Added {} wallets from disk.", added);
+        Ok(added)
+    }
+
+    /// Repairs the store of a wallet that cannot be loaded, e.g. after an
+    /// interrupted write. What is discarded is backed up next to the store.
+    /// Returns the number of bytes discarded.
+    #[allow(unused)]
+    pub async fn repair_store(&self, id: PublicKey) -> Result<u64> {
+        if self.in_memory {
+            return Ok(0);
+        }
+        // no store is opened for the wallet while it is repaired
+        let _self_lock = self.self_lock.lock().await;
+        if self.locks.contains_key(&id) {
+            return Err(Error::InvalidOperation(
+                "The store of a tracked wallet is loaded, and cannot be repaired.".to_string(),
+            ));
+        }
+        TransferStore::<ReplicaEvent>::repair(id.into(), &self.root_dir)
+    }
+
+    /// Prunes wallets that have a zero balance, and no debits, pending or registered.
+    /// The section wallet is never pruned. Wallets with debits are kept, so that their
+    /// history is still handed over by `user_wallets`, and their debit counter is never reset.
+    /// Wallets in use by another operation, or that cannot be loaded, are skipped.
+    /// Returns the ids of the pruned wallets.
+    #[allow(unused)]
+    pub async fn prune_empty_wallets(&self, policy: PrunePolicy) -> Result<Vec<PublicKey>> {
+        let section_wallet = self.crediting_key();
+        let mut pruned = vec![];
+        for id in self.wallet_ids() {
+            if id == section_wallet {
+                continue;
+            }
+            // no store is created for the wallet until its store is deleted,
+            // so that nothing is written to a store that is about to be deleted
+            let self_lock = self.self_lock.lock().await;
+            // Acquire lock of the wallet.
+            let key_lock = match self.load_key_lock(id).await {
+                Ok(key_lock) => key_lock,
+                // untracked since we listed the wallets
+                Err(_) => continue,
+            };
+            let mut store = key_lock.lock().await;
+
+            // Access to the specific wallet is now serialised!
+            let wallet = match self.load_wallet(&mut store, OwnerType::Single(id)).await {
+                Ok(wallet) => wallet,
+                Err(error) => {
+                    warn!("Replica: not pruning wallet {}: {}", id, error);
+                    continue;
+                }
+            };
+            // pending debits are debits too
+            let has_debits = store.get_all().iter().any(|e| {
+                matches!(
+                    e,
+                    ReplicaEvent::TransferValidated(_) | ReplicaEvent::TransferRegistered(_)
+                )
+            });
+            if has_debits || wallet.balance() != Token::zero() {
+                continue;
+            }
+            // only untrack the wallet if no one but us holds its lock,
+            // so that no other operation writes to the store once pruned
+            let removed = self
+                .locks
+                .remove_if(&id, |_, lock| Arc::strong_count(lock) == 2)
+                .is_some();
+            if !removed {
+                continue;
+            }
+            pruned.push(id);
+            if policy != PrunePolicy::Delete || self.in_memory {
+                continue;
+            }
+            // the store writes itself to disk when dropped, so it is dropped before deletion
+            drop(store);
+            drop(key_lock);
+            TransferStore::<ReplicaEvent>::delete(id.into(), &self.root_dir)?;
+            drop(self_lock);
+        }
+        info!("Pruned {} empty wallets.", pruned.len());
+        Ok(pruned)
+    }
+
+    /// Drops the wallets outside of our new prefix after a section split,
+    /// as the sibling section is now responsible for them. Their stores are
+    /// kept on disk. Returns the number of wallets retained.
+    pub async fn handle_section_split(&mut self, our_prefix: Prefix) -> Result<usize> {
+        let before = self.locks.len();
+        self.keep_keys_of(our_prefix).await?;
+        let retained = self.locks.len();
+        info!(
+            "Split to {:?}: retained {} of {} wallets.",
+            our_prefix, retained, before
+        );
+        Ok(retained)
+    }
+
+    #[allow(unused)]
+    pub async fn keep_keys_of(&self, prefix: Prefix) -> Result<()> {
+        // Removes keys that are no longer our section responsibility.
+        for key in self.wallet_ids() {
+            if !prefix.matches(&key.into()) {
+                let key_lock = self.load_key_lock(key).await?;
+                let _store = key_lock.lock().await;
+                let _ = self.locks.remove(&key);
+                // todo: remove db from disk
+            }
+        }
+        Ok(())
+    }
+
+    /// Step 1. Main business logic validation of a debit.
+    pub async fn validate(&self, signed_transfer: SignedTransfer) -> Result<TransferValidated> {
+        let id = signed_transfer.sender();
+        let transfer_id = signed_transfer.id();
+        debug!(
+            "Replica: validating transfer {:?} of wallet {}",
+            transfer_id, id
+        );
+        let result = self.try_validate(signed_transfer).await;
+        log_outcome("validate", id, transfer_id, &result);
+        result
+    }
+
+    async fn try_validate(&self, signed_transfer: SignedTransfer) -> Result<TransferValidated> {
+        let id = signed_transfer.sender();
+        // Acquire lock of the wallet.
+        let key_lock = self.load_key_lock(id).await?;
+        let mut store = key_lock.lock().await;
+
+        // Access to the specific wallet is now serialised!
+        let wallet = self.load_wallet(&mut store, OwnerType::Single(id)).await?;
+
+        debug!("Wallet loaded");
+        let _ = wallet.validate(&signed_transfer.debit, &signed_transfer.credit)?;
+
+        debug!("wallet valid");
+        // signing will be serialised
+        let (replica_debit_sig, replica_credit_sig) =
+            self.info.signing.sign_transfer(&signed_transfer).await?;
+        // release lock and update state
+        let event = TransferValidated {
+            signed_credit: signed_transfer.credit,
+            signed_debit: signed_transfer.debit,
+            replica_debit_sig,
+            replica_credit_sig,
+            replicas: self.info.peer_replicas.clone(),
+        };
+
+        // first store to disk
+        store.try_insert(ReplicaEvent::TransferValidated(event.clone()))?;
+        let mut wallet = wallet;
+        // then apply to inmem state
+        wallet.apply(ReplicaEvent::TransferValidated(event.clone()))?;
+
+        Ok(event)
+    }
+
+    /// Step 2. Validation of agreement, and order at debit source.
+    pub async fn register(
+        &self,
+        transfer_proof: &TransferAgreementProof,
+    ) -> Result<TransferRegistered> {
+        let id = transfer_proof.sender();
+        let transfer_id = transfer_proof.id();
+        debug!(
+            "Replica: registering transfer {:?} of wallet {}",
+            transfer_id, id
+        );
+        let result = self.try_register(transfer_proof).await;
+        log_outcome("register", id, transfer_id, &result);
+        result
+    }
+
+    async fn try_register(
+        &self,
+        transfer_proof: &TransferAgreementProof,
+    ) -> Result<TransferRegistered> {
+        let id = transfer_proof.sender();
+
+        // should only have been signed by our section
+        let known_key = exists_in_chain(
+            &self.info.section_chain,
+            &transfer_proof.replica_keys().public_key(),
+        );
+        if !known_key {
+            return Err(Error::Transfer(sn_transfers::Error::SectionKeyNeverExisted));
+        }
+
+        // Acquire lock of the wallet.
+        let key_lock = self.load_key_lock(id).await?;
+        let mut store = key_lock.lock().await;
+
+        // Access to the specific wallet is now serialised!
+        let wallet = self.load_wallet(&mut store, OwnerType::Single(id)).await?;
+        match wallet.register(transfer_proof)? {
+            None => {
+                info!("transfer already registered!");
+                Err(Error::TransferAlreadyRegistered)
+            }
+            Some(event) => {
+                // first store to disk
+                store.try_insert(ReplicaEvent::TransferRegistered(event.clone()))?;
+                let mut wallet = wallet;
+                // then apply to inmem state
+                wallet.apply(ReplicaEvent::TransferRegistered(event.clone()))?;
+                Ok(event)
+            }
+        }
+    }
+
+    /// Step 3. Validation of DebitAgreementProof, and credit idempotency at credit destination.
+    /// (Since this leads to a credit, there is no requirement on order.)
+    pub async fn receive_propagated(
+        &self,
+        debiting_replicas_name: XorName,
+        credit_proof: &CreditAgreementProof,
+    ) -> Result<TransferPropagated> {
+        let id = credit_proof.recipient();
+        let credit_id = *credit_proof.id();
+        debug!("Replica: receiving credit {:?} to wallet {}", credit_id, id);
+        let result = self
+            .try_receive_propagated(debiting_replicas_name, credit_proof)
+            .await;
+        log_outcome("receive_propagated", id, credit_id, &result);
+        result
+    }
+
+    /// Re-drives propagation of a credit, e.g. one that was registered at
+    /// its source, but never reached us. The proof is verified first, and
+    /// repeated calls do not store the credit again.
+    #[allow(unused)]
+    pub async fn force_propagate(
+        &self,
+        credit_proof: CreditAgreementProof,
+    ) -> Result<TransferPropagated> {
+        let valid = verify_sig(
+            &credit_proof.debiting_replicas_keys,
+            &credit_proof.debiting_replicas_sig,
+            &credit_proof.signed_credit,
+        )?;
+        if !valid {
+            return Err(Error::InvalidPropagatedTransfer(credit_proof));
+        }
+        let debiting_replicas = PublicKey::Bls(credit_proof.replica_keys().public_key());
+        info!(
+            "Replica: forcing propagation of credit {:?}",
+            credit_proof.id()
+        );
+        self.receive_propagated(debiting_replicas.into(), &credit_proof)
+            .await
+    }
+
+    async fn try_receive_propagated(
+        &self,
+        _debiting_replicas_name: XorName,
+        credit_proof: &CreditAgreementProof,
+    ) -> Result<TransferPropagated> {
+        // Acquire lock of the wallet.
+        let id = credit_proof.recipient();
+        let _debiting_replicas_key = credit_proof.replica_keys().public_key();
+
+        // TODO: check the debiting_replicas_key, needs reverse AE implemented
+
+        // Replays of recently propagated credits are answered without loading the wallet,
+        // once their proof is verified.
+        // Only ids of credits that were successfully propagated are remembered.
+        if self.recent_credits.lock().await.contains(credit_proof.id()) {
+            let valid = verify_sig(
+                &credit_proof.debiting_replicas_keys,
+                &credit_proof.debiting_replicas_sig,
+                &credit_proof.signed_credit,
+            )?;
+            if !valid {
+                return Err(Error::InvalidPropagatedTransfer(credit_proof.clone()));
+            }
+            debug!(
+                "Replica: credit {:?} was already propagated",
+                credit_proof.id()
+            );
+            self.recent_credits.lock().await.insert(*credit_proof.id());
+            return Ok(TransferPropagated {
+                credit_proof: credit_proof.clone(),
+            });
+        }
+
+        // Only when propagated is there a risk that the store doesn't exist,
+        // and that we want to create it. All other write operations require that
+        // a propagation has occurred first. Read ops simply return error when it doesn't exist.
+        let key_lock = match self.load_key_lock(id).await {
+            Ok(key_lock) => key_lock,
+            Err(_) => {
+                // lock on us, but only when store doesn't exist
+                let self_lock = self.self_lock.lock().await;
+                match self.load_key_lock(id).await {
+                    Ok(store) => store,
+                    Err(_) => {
+                        // no key lock (hence no store), so we create one
+                        let store = self.create_store(id)?;
+                        let locked_store = Arc::new(Mutex::new(store));
+                        let _ = self.locks.insert(id, locked_store.clone());
+                        let _ = self_lock.overflowing_add(0); // resolve: is a usage at end of block necessary to actually engage the lock?
+                        locked_store
+                    }
+                }
+            }
+        };
+
+        let mut store = key_lock.lock().await;
+
+        // Access to the specific wallet is now serialised!
+        let wallet = self.load_wallet(&mut store, OwnerType::Single(id)).await?;
+        let propagation_result = wallet.receive_propagated(credit_proof);
+        if propagation_result.is_ok() {
+            // update state
+            let event = TransferPropagated {
+                credit_proof: credit_proof.clone(),
+            };
+            // only add it locally if we don't know about it... (this prevents SimulatedPayouts being reapplied due to varied sigs.)
+            if propagation_result?.is_some() {
+                // first store to disk
+                store.try_insert(ReplicaEvent::TransferPropagated(event.clone()))?;
+                let mut wallet = wallet;
+                // then apply to inmem state
+                wallet.apply(ReplicaEvent::TransferPropagated(event.clone()))?;
+            }
+            self.recent_credits.lock().await.insert(*credit_proof.id());
+            return Ok(event);
+        }
+        Err(Error::InvalidPropagatedTransfer(credit_proof.clone()))
+    }
+
+    async fn load_key_lock(
+        &self,
+        id: PublicKey,
+    ) -> Result<Arc<Mutex<TransferStore<ReplicaEvent>>>> {
+        match self.locks.get(&id) {
+            Some(val) => Ok(val.clone()),
+            None => Err(Error::Logic("Key does not exist among locks.".to_string())),
+        }
+    }
+
+    /// Runs the query on the store of the wallet, if it has one.
+    /// The store of a tracked wallet is read under its lock, so that
+    /// only committed events are seen. Other stores are read from disk,
+    /// without ever writing to them.
+    /// The store is loaded and queried on a blocking thread, so that
+    /// queries of several stores can run in parallel.
+    async fn query_store<R: Send + 'static>(
+        &self,
+        id: PublicKey,
+        query: impl FnOnce(&mut TransferStore<ReplicaEvent>) -> R + Send + 'static,
+    ) -> Result<Option<R>> {
+        let key_lock = self.locks.get(&id).map(|key_lock| key_lock.clone());
+        let in_memory = self.in_memory;
+        let root_dir = self.root_dir.clone();
+        let load = move || {
+            if let Some(key_lock) = key_lock {
+                let mut store = futures::executor::block_on(key_lock.lock());
+                return Ok(Some(query(&mut store)));
+            }
+            if in_memory || !TransferStore::<ReplicaEvent>::exists(id.into(), &root_dir)? {
+                return Ok(None);
+            }
+            let mut store = TransferStore::open_read_only(id.into(), &root_dir)?;
+            Ok(Some(query(&mut store)))
+        };
+        tokio::task::spawn_blocking(load)
+            .await
+            .map_err(|error| Error::Logic(format!("Store query failed: {}", error)))?
+    }
+
+    /// The verified events of the wallet, if it has a store.
+    async fn query_verified_events(&self, id: PublicKey) -> Result<Option<Vec<ReplicaEvent>>> {
+        let section_chain = self.info.section_chain.clone();
+        self.query_store(id, move |store| verified_events(&section_chain, store))
+            .await?
+            .transpose()
+    }
+
+    /// Runs the query on the stores of all tracked wallets, loading
+    /// up to `load_concurrency` stores at a time.
+    /// The results are ordered by wallet id.
+    async fn query_all_stores<R: Send + 'static>(
+        &self,
+        query: impl Fn(&mut TransferStore<ReplicaEvent>) -> R + Clone + Send + 'static,
+    ) -> Vec<(PublicKey, Result<Option<R>>)> {
+        use futures::stream::{self, StreamExt};
+        let mut results: Vec<_> = stream::iter(self.wallet_ids())
+            .map(|id| {
+                let query = query.clone();
+                async move { (id, self.query_store(id, query).await) }
+            })
+            .buffer_unordered(self.load_concurrency)
+            .collect()
+            .await;
+        results.sort_by_key(|(id, _)| *id);
+        results
+    }
+
+    /// A new store for the wallet, in memory or on disk.
+    fn create_store(&self, id: PublicKey) -> Result<TransferStore<ReplicaEvent>> {
+        if self.in_memory {
+            Ok(TransferStore::in_memory())
+        } else {
+            let mut store = TransferStore::new(id.into(), &self.root_dir)?;
+            store.set_durability(self.durability);
+            Ok(store)
+        }
+    }
+
+    async fn load_wallet(
+        &self,
+        store: &mut TransferStore<ReplicaEvent>,
+        id: OwnerType,
+    ) -> Result<WalletReplica> {
+        let events = verified_events(&self.info.section_chain, store)?;
+        self.wallet_from_events(id, events)
+    }
+
+    /// Verifies the events, and applies them to a new wallet.
+    fn wallet_from_history(
+        &self,
+        id: OwnerType,
+        events: Vec<ReplicaEvent>,
+    ) -> Result<WalletReplica> {
+        verify_events(&self.info.section_chain, &events, 0)?;
+        self.wallet_from_events(id, events)
+    }
+
+    /// Applies already verified events to a new wallet.
+    fn wallet_from_events(
+        &self,
+        id: OwnerType,
+        events: Vec<ReplicaEvent>,
+    ) -> Result<WalletReplica> {
+        let wallet = WalletReplica::from_history(
+            id,
+            self.info.id,
+            self.info.key_index,
+            self.info.peer_replicas.clone(),
+            events,
+        )?;
+        Ok(wallet)
+    }
+
+    /// Verifies that a history is that of the wallet,
+    /// that its events are validly signed, and apply in order.
+    fn verify_history(&self, id: PublicKey, events: &[ReplicaEvent]) -> Result<()> {
+        if events.iter().any(|e| wallet_id_of(e) != Some(id)) {
+            return Err(Error::InvalidOperation(
+                "History must contain only events of a single wallet.".to_string(),
+            ));
+        }
+        // applying events to a wallet does not check the order of debits, but a store does
+        let mut store = TransferStore::in_memory();
+        for event in events {
+            store.try_insert(event.clone())?;
+        }
+        let _ = self.wallet_from_history(OwnerType::Single(id), events.to_vec())?;
+        Ok(())
+    }
+
+    async fn get_load_or_create_store(
+        &self,
+        id: PublicKey,
+    ) -> Result<Arc<Mutex<TransferStore<ReplicaEvent>>>> {
+        let self_lock = self.self_lock.lock().await;
+        // get or create the store for PK.
+        let key_lock = match self.load_key_lock(id).await {
+            Ok(lock) => lock,
+            Err(_) => {
+                let store = match self.create_store(id) {
+                    Ok(store) => store,
+                    // no key lock, so we create one for this payout...
+                    Err(_e) => self.create_store(id)?,
+                };
+                let locked_store = Arc::new(Mutex::new(store));
+                let _ = self.locks.insert(id, locked_store.clone());
+                locked_store
+            }
+        };
+        let _ = self_lock.overflowing_add(0); // resolve: is a usage at end of block necessary to actually engage the lock?
+
+        Ok(key_lock)
+    }
+
+    // ------------------------------------------------------------------
+    //  --------------------  Simulated Payouts ------------------------
+    // ------------------------------------------------------------------
+
+    #[cfg(feature = "simulated-payouts")]
+    pub async fn credit_without_proof(&self, transfer: Transfer) -> Result<NodeDuty> {
+        debug!("Performing credit without proof");
+
+        let debit = transfer.debit();
+
+        debug!("provided debit {:?}", debit);
+        let credit = transfer.credit()?;
+
+        debug!("provided credit {:?}", credit);
+
+        // Acquire lock of the wallet.
+        let id = transfer.to;
+
+        let store = self.get_load_or_create_store(id).await?;
+        let mut store = store.lock().await;
+
+        let mut wallet = self.load_wallet(&mut store, OwnerType::Single(id)).await?;
+
+        debug!("wallet loaded");
+        wallet.credit_without_proof(credit.clone())?;
+
+        // let debit_store = self.get_load_or_create_store(debit.id().actor).await?;
+        // let mut debit_store = debit_store.lock().await;
+        // // Access to the specific wallet is now serialised!
+        // let mut debit_wallet = self.load_wallet(&debit_store, debit.id().actor).await?;
+        // debit_wallet.debit_without_proof(debit.clone())?;
+
+        // use the threshold of our replicas, so that the proof resembles a real one
+        let mut rng = thread_rng();
+        let sec_key_set = SecretKeySet::random(self.info.peer_replicas.threshold(), &mut rng);
+        let sec_key = SecretKey::random();
+        let actor_signature = Signature::from(sec_key.sign(utils::serialise(&credit)?));
+        let signed_credit = SignedCredit {
+            credit,
+            actor_signature,
+        };
+        // sign with a quorum of the dummy replicas, so that the proof verifies when the wallet is loaded
+        let serialised_credit = utils::serialise(&signed_credit)?;
+        let credit_sig_shares: BTreeMap<_, _> = (0..=sec_key_set.threshold())
+            .map(|index| {
+                let share = sec_key_set.secret_key_share(index).sign(&serialised_credit);
+                (index, share)
+            })
+            .collect();
+        let debiting_replicas_keys = sec_key_set.public_keys();
+        let debiting_replicas_sig = Signature::Bls(
+            debiting_replicas_keys
+                .combine_signatures(&credit_sig_shares)
+                .map_err(|_| Error::CouldNotCombineSignatures)?,
+        );
+        let credit_proof = CreditAgreementProof {
+            signed_credit,
+            debiting_replicas_sig,
+            debiting_replicas_keys,
+        };
+
+        store.try_insert(ReplicaEvent::TransferPropagated(TransferPropagated {
+            credit_proof,
+        }))?;
+
+        Ok(NodeDuty::NoOp)
+    }
+}
+
+/// Combines the signature shares of validations of the same transfer,
+/// into a proof of agreement, once a quorum of replicas have validated it.
+/// Every share is verified, so that one invalid share is reported as such,
+/// instead of failing the combined signature.
+#[allow(unused)]
+pub fn combine_signatures(validations: &[TransferValidated]) -> Result<TransferAgreementProof> {
+    let first = validations
+        .first()
+        .ok_or_else(|| Error::InvalidOperation("No validations to combine.".to_string()))?;
+    let consistent = validations.iter().all(|v| {
+        v.signed_debit == first.signed_debit
+            && v.signed_credit == first.signed_credit
+            && v.replicas == first.replicas
+    });
+    if !consistent {
+        return Err(Error::InvalidOperation(
+            "Validations are not of the same transfer and replicas.".to_string(),
+        ));
+    }
+    for validation in validations {
+        if !has_valid_shares(validation)? {
+            return Err(Error::InvalidOperation(format!(
+                "Invalid signature share of replica {}.",
+                validation.replica_debit_sig.index
+            )));
+        }
+    }
+    let debit_shares: BTreeMap<_, _> = validations
+        .iter()
+        .map(|v| (v.replica_debit_sig.index, v.replica_debit_sig.share.clone()))
+        .collect();
+    let credit_shares: BTreeMap<_, _> = validations
+        .iter()
+        .map(|v| {
+            (
+                v.replica_credit_sig.index,
+                v.replica_credit_sig.share.clone(),
+            )
+        })
+        .collect();
+    if debit_shares.len() <= first.replicas.threshold() {
+        return Err(Error::InvalidOperation(format!(
+            "Not enough validations: {} of {} needed.",
+            debit_shares.len(),
+            first.replicas.threshold() + 1
+        )));
+    }
+    let debit_sig = first
+        .replicas
+        .combine_signatures(&debit_shares)
+        .map_err(|_| Error::CouldNotCombineSignatures)?;
+    let credit_sig = first
+        .replicas
+        .combine_signatures(&credit_shares)
+        .map_err(|_| Error::CouldNotCombineSignatures)?;
+    Ok(TransferAgreementProof {
+        signed_debit: first.signed_debit.clone(),
+        signed_credit: first.signed_credit.clone(),
+        debit_sig: Signature::Bls(debit_sig),
+        credit_sig: Signature::Bls(credit_sig),
+        debiting_replicas_keys: first.replicas.clone(),
+    })
+}
+
+/// Inserts the event, skipping it if it is already stored.
+/// Any other conflict with the stored events is an error.
+fn insert_unless_stored(
+    store: &mut TransferStore<ReplicaEvent>,
+    event: ReplicaEvent,
+) -> Result<()> {
+    if let Some(id) = event.event_id() {
+        if store.get_by_id(&id).as_ref() == Some(&event) {
+            return Ok(());
+        }
+    }
+    store.try_insert(event)
+}
+
+/// Logs the outcome of an operation on a wallet.
+/// Only ids are logged, never the signatures.
+fn log_outcome<R, I: Debug>(op: &str, id: PublicKey, transfer_id: I, result: &Result<R>) {
+    match result {
+        Ok(_) => debug!(
+            "Replica: {} of {:?} for wallet {} succeeded",
+            op, transfer_id, id
+        ),
+        Err(error) => debug!(
+            "Replica: {} of {:?} for wallet {} failed: {}",
+            op, transfer_id, id, error
+        ),
+    }
+}
+
+/// The wallet that an event belongs to.
+fn wallet_id_of(event: &ReplicaEvent) -> Option<PublicKey> {
+    match event {
+        ReplicaEvent::TransferValidated(e) => Some(e.signed_debit.sender()),
+        ReplicaEvent::TransferRegistered(e) => Some(e.transfer_proof.sender()),
+        ReplicaEvent::TransferPropagated(e) => Some(e.credit_proof.recipient()),
+        _ => None,
+    }
+}
+
+/// Appends the events of a verified history that extends the stored events.
+/// If an append fails, the store is truncated back to the events it had.
+fn extend_history(
+    store: &mut TransferStore<ReplicaEvent>,
+    events: Vec<ReplicaEvent>,
+) -> Result<()> {
+    let len = store.len();
+    for event in events.into_iter().skip(len) {
+        if let Err(error) = store.try_insert(event) {
+            if let Err(rollback_error) = store.truncate(len) {
+                warn!("Replica: could not roll back an import: {}", rollback_error);
+            }
+            return Err(error);
+        }
+    }
+    // the stored events are now exactly those of the verified history
+    let count = store.len();
+    store.set_verified(count);
+    Ok(())
+}
+
+/// Whether a history conflicts with the stored events of the wallet,
+/// i.e. does not extend them.
+fn conflicts_with_stored(existing: &[ReplicaEvent], events: &[ReplicaEvent]) -> bool {
+    existing.len() > events.len() || existing.iter().zip(events.iter()).any(|(a, b)| a != b)
+}
+
+fn history_conflict() -> Error {
+    Error::InvalidOperation("History conflicts with the stored events.".to_string())
+}
+
+/// The events of the store, once those appended since
+/// it was last verified have been verified.
+fn verified_events(
+    section_chain: &SectionChain,
+    store: &mut TransferStore<ReplicaEvent>,
+) -> Result<Vec<ReplicaEvent>> {
+    let events = store.get_all();
+    verify_events(section_chain, &events, store.verified())?;
+    store.set_verified(events.len());
+    Ok(events)
+}
+
+/// Verifies the signatures of the events in a wallet history from
+/// index `from`, so that a tampered store is never loaded into a wallet.
+fn verify_events(section_chain: &SectionChain, events: &[ReplicaEvent], from: usize) -> Result<()> {
+    for (index, event) in events.iter().enumerate().skip(from) {
+        if !is_valid_event(section_chain, event)? {
+            return Err(Error::CorruptStore(format!(
+                "Invalid signature in event at index {}",
+                index
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Whether the signature shares of the validation are those of its replica.
+fn has_valid_shares(validation: &TransferValidated) -> Result<bool> {
+    let debit_key = validation
+        .replicas
+        .public_key_share(validation.replica_debit_sig.index);
+    let credit_key = validation
+        .replicas
+        .public_key_share(validation.replica_credit_sig.index);
+    Ok(debit_key.verify(
+        &validation.replica_debit_sig.share,
+        utils::serialise(&validation.signed_debit)?,
+    ) && credit_key.verify(
+        &validation.replica_credit_sig.share,
+        utils::serialise(&validation.signed_credit)?,
+    ))
+}
+
+fn is_valid_event(section_chain: &SectionChain, event: &ReplicaEvent) -> Result<bool> {
+    match event {
+        ReplicaEvent::TransferValidated(e) => {
+            Ok(exists_in_chain(section_chain, &e.replicas.public_key()) && has_valid_shares(e)?)
+        }
+        ReplicaEvent::TransferRegistered(e) => {
+            let proof = &e.transfer_proof;
+            let keys = &proof.debiting_replicas_keys;
+            Ok(exists_in_chain(section_chain, &keys.public_key())
+                && verify_sig(keys, &proof.debit_sig, &proof.signed_debit)?
+                && verify_sig(keys, &proof.credit_sig, &proof.signed_credit)?)
+        }
+        ReplicaEvent::TransferPropagated(e) => {
+            // TODO: check the debiting replicas key, needs reverse AE implemented
+            let proof = &e.credit_proof;
+            verify_sig(
+                &proof.debiting_replicas_keys,
+                &proof.debiting_replicas_sig,
+                &proof.signed_credit,
+            )
+        }
+        // other events carry no replica signatures
+        _ => Ok(true),
+    }
+}
+
+fn exists_in_chain(section_chain: &SectionChain, key: &bls::PublicKey) -> bool {
+    section_chain.keys().any(|key_in_chain| key_in_chain == key)
+}
+
+fn verify_sig<T: Serialize>(keys: &PublicKeySet, sig: &Signature, data: &T) -> Result<bool> {
+    match sig {
+        Signature::Bls(sig) => Ok(keys.public_key().verify(sig, utils::serialise(data)?)),
+        _ => Ok(false),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::transfers::{store::TRANSFERS_DIR_NAME, test_utils::TestReplicaSigning};
+    use bls::{SecretKey, SecretKeySet};
+    use rand::thread_rng;
+    use sn_data_types::{Credit, DebitId, SignatureShare, SignedCredit, SignedDebit, Transfer};
+    use sn_transfers::Error as TransferError;
+    use std::collections::BTreeSet;
+    use tempdir::TempDir;
+
+    #[tokio::test]
+    async fn loads_wallet_with_valid_events() -> Result<()> {
+        let (replicas, secret_key_set) = get_replicas().await?;
+        let wallet_id = get_random_pk();
+        let credit_proof = get_credit_proof(10, wallet_id, &secret_key_set)?;
+        let _ = replicas
+            .receive_propagated(XorName::random(), &credit_proof)
+            .await?;
+
+        assert_eq!(replicas.balance(wallet_id).await?, Token::from_nano(10));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn loading_corrupt_store_fails() -> Result<()> {
+        let (replicas, secret_key_set) = get_replicas().await?;
+        let wallet_id = get_random_pk();
+        let mut credit_proof = get_credit_proof(10, wallet_id, &secret_key_set)?;
+        credit_proof.debiting_replicas_sig = Signature::Bls(SecretKey::random().sign(b"tampered"));
+
+        let mut store = TransferStore::new(wallet_id.into(), &replicas.root_dir)?;
+        store.try_insert(ReplicaEvent::TransferPropagated(TransferPropagated {
+            credit_proof,
+        }))?;
+
+        match replicas.balance(wallet_id).await {
+            Err(Error::CorruptStore(_)) => Ok(()),
+            other => Err(Error::Logic(format!("Unexpected result: {:?}", other))),
+        }
+    }
+
+    #[tokio::test]
+    async fn merge_skips_stored_events_and_rejects_conflicts() -> Result<()> {
+        let (mut replicas, secret_key_set) = get_replicas().await?;
+        let sender = SecretKey::random();
+        let sender_id = PublicKey::from(sender.public_key());
+        let credit_proof = get_credit_proof(10, sender_id, &secret_key_set)?;
+        let transfer = get_signed_transfer(&sender, 0, 4, get_random_pk())?;
+        let history = ActorHistory {
+            credits: vec![credit_proof.clone()],
+            debits: vec![get_transfer_proof(transfer, &secret_key_set)?],
+        };
+        replicas
+            .merge(vec![(sender_id, history.clone())].into_iter().collect())
+            .await?;
+        // merging the same history again stores nothing twice
+        replicas
+            .merge(vec![(sender_id, history)].into_iter().collect())
+            .await?;
+        assert_eq!(get_events(&replicas, sender_id)?.len(), 2);
+
+        // a different debit with the same id, and one out of order
+        for counter in &[0, 2] {
+            let transfer = get_signed_transfer(&sender, *counter, 4, get_random_pk())?;
+            let history = ActorHistory {
+                credits: vec![credit_proof.clone()],
+                debits: vec![get_transfer_proof(transfer, &secret_key_set)?],
+            };
+            assert!(matches!(
+                replicas
+                    .merge(vec![(sender_id, history)].into_iter().collect())
+                    .await,
+                Err(Error::ConflictingEvent(_))
+            ));
+        }
+        assert_eq!(get_events(&replicas, sender_id)?.len(), 2);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn merge_rejects_histories_that_fail_verification() -> Result<()> {
+        let (mut replicas, secret_key_set) = get_replicas().await?;
+        let wallet_id = get_random_pk();
+        let mut forged = get_credit_proof(10, wallet_id, &secret_key_set)?;
+        forged.debiting_replicas_sig = Signature::Bls(SecretKey::random().sign(b"forged"));
+        let history = ActorHistory {
+            credits: vec![forged],
+            debits: vec![],
+        };
+        assert!(replicas
+            .merge(vec![(wallet_id, history)].into_iter().collect())
+            .await
+            .is_err());
+        assert!(!replicas.knows_wallet(&wallet_id));
+
+        // the wallet is still usable
+        let credit_proof = get_credit_proof(10, wallet_id, &secret_key_set)?;
+        let _ = replicas
+            .receive_propagated(XorName::random(), &credit_proof)
+            .await?;
+        assert_eq!(replicas.balance(wallet_id).await?, Token::from_nano(10));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn displayed_balances_round_trip() -> Result<()> {
+        for amount in &[0, 1, u64::MAX] {
+            let (replicas, secret_key_set) = get_replicas().await?;
+            let wallet_id = get_random_pk();
+            if *amount > 0 {
+                let credit_proof = get_credit_proof(*amount, wallet_id, &secret_key_set)?;
+                let _ = replicas
+                    .receive_propagated(XorName::random(), &credit_proof)
+                    .await?;
+            }
+            let expected = Token::from_nano(*amount);
+            let balance: Token = replicas.balance_display(wallet_id).await?.parse()?;
+            assert_eq!(balance, expected);
+            let managed: Token = replicas.managed_amount_display().await?.parse()?;
+            assert_eq!(managed, expected);
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn wallet_ids_include_propagated_recipients() -> Result<()> {
+        let (replicas, secret_key_set) = get_replicas().await?;
+        let mut expected = BTreeSet::new();
+        for _ in 0..3 {
+            let wallet_id = get_random_pk();
+            let credit_proof = get_credit_proof(10, wallet_id, &secret_key_set)?;
+            let _ = replicas
+                .receive_propagated(XorName::random(), &credit_proof)
+                .await?;
+            let _ = expected.insert(wallet_id);
+        }
+
+        let actual: BTreeSet<_> = replicas.wallet_ids().into_iter().collect();
+        assert_eq!(actual, expected);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn can_validate_writes_no_events() -> Result<()> {
+        let (replicas, secret_key_set) = get_replicas().await?;
+        let sender = SecretKey::random();
+        let sender_id = PublicKey::from(sender.public_key());
+        let credit_proof = get_credit_proof(10, sender_id, &secret_key_set)?;
+        let _ = replicas
+            .receive_propagated(XorName::random(), &credit_proof)
+            .await?;
+
+        let fundable = get_signed_transfer(&sender, 0, 10, get_random_pk())?;
+        replicas.can_validate(&fundable).await?;
+
+        let overspend = get_signed_transfer(&sender, 0, 11, get_random_pk())?;
+        assert!(replicas.can_validate(&overspend).await.is_err());
+
+        let validated = get_events(&replicas, sender_id)?
+            .into_iter()
+            .filter(|e| matches!(e, ReplicaEvent::TransferValidated(_)))
+            .count();
+        assert_eq!(validated, 0);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn rejects_self_and_zero_amount_transfers() -> Result<()> {
+        let (replicas, secret_key_set) = get_replicas().await?;
+        let sender = SecretKey::random();
+        let sender_id = PublicKey::from(sender.public_key());
+        let credit_proof = get_credit_proof(10, sender_id, &secret_key_set)?;
+        let _ = replicas
+            .receive_propagated(XorName::random(), &credit_proof)
+            .await?;
+
+        let to_self = get_signed_transfer(&sender, 0, 5, sender_id)?;
+        assert!(matches!(
+            replicas.can_validate(&to_self).await,
+            Err(Error::Transfer(TransferError::SameSenderAndRecipient))
+        ));
+        assert!(matches!(
+            replicas.validate(to_self).await,
+            Err(Error::Transfer(TransferError::SameSenderAndRecipient))
+        ));
+
+        let zero_amount = get_signed_transfer(&sender, 0, 0, get_random_pk())?;
+        assert!(matches!(
+            replicas.can_validate(&zero_amount).await,
+            Err(Error::Transfer(TransferError::ZeroValueTransfer))
+        ));
+        assert!(matches!(
+            replicas.validate(zero_amount).await,
+            Err(Error::Transfer(TransferError::ZeroValueTransfer))
+        ));
+
+        assert_eq!(get_events(&replicas, sender_id)?.len(), 1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn balance_of_unknown_wallet_creates_no_store() -> Result<()> {
+        let (replicas, _) = get_replicas().await?;
+        let wallet_id = get_random_pk();
+
+        assert_eq!(replicas.balance(wallet_id).await?, Token::from_nano(0));
+        assert_eq!(std::fs::read_dir(&replicas.root_dir)?.count(), 0);
+        assert!(replicas.wallet_ids().is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn history_of_unknown_wallet_creates_no_store() -> Result<()> {
+        let (replicas, _) = get_replicas().await?;
+        let wallet_id = get_random_pk();
+
+        let history = replicas.history(wallet_id).await?;
+        assert!(history.credits.is_empty());
+        assert!(history.debits.is_empty());
+        assert_eq!(std::fs::read_dir(&replicas.root_dir)?.count(), 0);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn rebuild_locks_adds_stores_written_out_of_band() -> Result<()> {
+        let (mut replicas, secret_key_set) = get_replicas().await?;
+        let wallet_id = get_random_pk();
+        let credit_proof = get_credit_proof(10, wallet_id, &secret_key_set)?;
+        let mut store = TransferStore::new(wallet_id.into(), &replicas.root_dir)?;
+        store.try_insert(ReplicaEvent::TransferPropagated(TransferPropagated {
+            credit_proof,
+        }))?;
+        assert!(replicas.wallet_ids().is_empty());
+
+        assert_eq!(replicas.rebuild_locks_from_disk().await?, 1);
+        assert_eq!(replicas.wallet_ids(), vec![wallet_id]);
+        assert_eq!(replicas.balance(wallet_id).await?, Token::from_nano(10));
+        // already known stores are not added again
+        assert_eq!(replicas.rebuild_locks_from_disk().await?, 0);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn rebuild_locks_skips_stores_that_fail_verification() -> Result<()> {
+        let (mut replicas, secret_key_set) = get_replicas().await?;
+        let wallet_id = get_random_pk();
+        let mut credit_proof = get_credit_proof(10, wallet_id, &secret_key_set)?;
+        credit_proof.debiting_replicas_sig = Signature::Bls(SecretKey::random().sign(b"forged"));
+        let mut store = TransferStore::new(wallet_id.into(), &replicas.root_dir)?;
+        store.try_insert(ReplicaEvent::TransferPropagated(TransferPropagated {
+            credit_proof,
+        }))?;
+        drop(store);
+
+        assert_eq!(replicas.rebuild_locks_from_disk().await?, 0);
+        assert!(replicas.wallet_ids().is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn rebuild_locks_skips_stores_that_cannot_be_loaded() -> Result<()> {
+        let (mut replicas, secret_key_set) = get_replicas().await?;
+        let wallet_id = get_random_pk();
+        let credit_proof = get_credit_proof(10, wallet_id, &secret_key_set)?;
+        let mut store = TransferStore::new(wallet_id.into(), &replicas.root_dir)?;
+        store.try_insert(propagated(credit_proof))?;
+        drop(store);
+        let torn_id = get_random_pk();
+        let path = TransferStore::<ReplicaEvent>::db_path(torn_id.into(), &replicas.root_dir)?;
+        std::fs::create_dir_all(replicas.root_dir.join(TRANSFERS_DIR_NAME))?;
+        std::fs::write(&path, b"torn")?;
+
+        assert_eq!(replicas.rebuild_locks_from_disk().await?, 1);
+        assert_eq!(replicas.wallet_ids(), vec![wallet_id]);
+        // the unloadable store is left as it was, for repair
+        assert_eq!(std::fs::read(&path)?, b"torn");
+        Ok(())
+    }
+
+    #[cfg(feature = "simulated-payouts")]
+    #[tokio::test]
+    async fn simulated_credit_uses_replica_threshold() -> Result<()> {
+        let (replicas, _) = get_replicas().await?;
+        let wallet_id = get_random_pk();
+        let transfer = Transfer {
+            debit_id: DebitId::new(get_random_pk(), 0),
+            amount: Token::from_nano(10),
+            to: wallet_id,
+            msg: "simulated".to_string(),
+        };
+        let _ = replicas.credit_without_proof(transfer).await?;
+
+        match get_events(&replicas, wallet_id)?.as_slice() {
+            [ReplicaEvent::TransferPropagated(e)] => assert_eq!(
+                e.credit_proof.debiting_replicas_keys.threshold(),
+                replicas.info.peer_replicas.threshold()
+            ),
+            other => {
+                return Err(Error::Logic(format!("Unexpected events: {:?}", other)));
+            }
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn knows_wallet_is_distinct_from_store_on_disk() -> Result<()> {
+        let (replicas, secret_key_set) = get_replicas().await?;
+
+        // on disk only
+        let on_disk = get_random_pk();
+        let credit_proof = get_credit_proof(10, on_disk, &secret_key_set)?;
+        let mut store = TransferStore::new(on_disk.into(), &replicas.root_dir)?;
+        store.try_insert(ReplicaEvent::TransferPropagated(TransferPropagated {
+            credit_proof,
+        }))?;
+        assert!(!replicas.knows_wallet(&on_disk));
+        assert!(replicas.store_exists_on_disk(&on_disk).await);
+
+        // in memory only
+        let in_memory = get_random_pk();
+        let credit_proof = get_credit_proof(10, in_memory, &secret_key_set)?;
+        let _ = replicas
+            .receive_propagated(XorName::random(), &credit_proof)
+            .await?;
+        std::fs::remove_dir_all(&replicas.root_dir)?;
+        assert!(replicas.knows_wallet(&in_memory));
+        assert!(!replicas.store_exists_on_disk(&in_memory).await);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn validate_logs_the_sender() -> Result<()> {
+        init_test_logger();
+        let (replicas, secret_key_set) = get_replicas().await?;
+        let sender = SecretKey::random();
+        let sender_id = PublicKey::from(sender.public_key());
+        let credit_proof = get_credit_proof(10, sender_id, &secret_key_set)?;
+        let _ = replicas
+            .receive_propagated(XorName::random(), &credit_proof)
+            .await?;
+
+        let transfer = get_signed_transfer(&sender, 0, 10, get_random_pk())?;
+        let _ = replicas.validate(transfer).await?;
+
+        let expected = format!(
+            "validate of {:?} for wallet {}",
+            DebitId::new(sender_id, 0),
+            sender_id
+        );
+        let logged = TEST_LOGGER
+            .0
+            .lock()
+            .map_err(|e| Error::Logic(e.to_string()))?;
+        assert!(logged.iter().any(|line| line.contains(&expected)));
+        Ok(())
+    }
+
+    struct TestLogger(std::sync::Mutex<Vec<String>>);
+
+    impl log::Log for TestLogger {
+        fn enabled(&self, _: &log::Metadata) -> bool {
+            true
+        }
+
+        fn log(&self, record: &log::Record) {
+            if let Ok(mut lines) = self.0.lock() {
+                lines.push(record.args().to_string());
+            }
+        }
+
+        fn flush(&self) {}
+    }
+
+    lazy_static::lazy_static! {
+        static ref TEST_LOGGER: TestLogger = TestLogger(std::sync::Mutex::new(vec![]));
+    }
+
+    fn init_test_logger() {
+        // the logger can only be set once per process
+        if log::set_logger(&*TEST_LOGGER).is_ok() {
+            log::set_max_level(log::LevelFilter::Debug);
+        }
+    }
+
+    #[tokio::test]
+    async fn imports_clean_history() -> Result<()> {
+        let (replicas, secret_key_set) = get_replicas().await?;
+        let wallet_id = get_random_pk();
+        let history = vec![
+            propagated(get_credit_proof(10, wallet_id, &secret_key_set)?),
+            propagated(get_credit_proof(5, wallet_id, &secret_key_set)?),
+        ];
+
+        replicas.import_history(wallet_id, history.clone()).await?;
+        assert_eq!(replicas.balance(wallet_id).await?, Token::from_nano(15));
+        // importing the same history again is a no-op
+        replicas.import_history(wallet_id, history).await?;
+        assert_eq!(get_events(&replicas, wallet_id)?.len(), 2);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn rejects_conflicting_history() -> Result<()> {
+        let (replicas, secret_key_set) = get_replicas().await?;
+        let wallet_id = get_random_pk();
+        let credit_proof = get_credit_proof(10, wallet_id, &secret_key_set)?;
+        let _ = replicas
+            .receive_propagated(XorName::random(), &credit_proof)
+            .await?;
+
+        let history = vec![propagated(get_credit_proof(
+            20,
+            wallet_id,
+            &secret_key_set,
+        )?)];
+        assert!(replicas.import_history(wallet_id, history).await.is_err());
+        assert_eq!(replicas.balance(wallet_id).await?, Token::from_nano(10));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn rejects_history_with_debits_out_of_order() -> Result<()> {
+        let (replicas, secret_key_set) = get_replicas().await?;
+        let sender = SecretKey::random();
+        let sender_id = PublicKey::from(sender.public_key());
+        let mut history = vec![propagated(get_credit_proof(
+            10,
+            sender_id,
+            &secret_key_set,
+        )?)];
+        for counter in &[0, 2] {
+            let transfer = get_signed_transfer(&sender, *counter, 1, get_random_pk())?;
+            history.push(ReplicaEvent::TransferRegistered(TransferRegistered {
+                transfer_proof: get_transfer_proof(transfer, &secret_key_set)?,
+            }));
+        }
+
+        assert!(matches!(
+            replicas.import_history(sender_id, history).await,
+            Err(Error::ConflictingEvent(_))
+        ));
+        // a rejected import leaves no store behind
+        assert!(!replicas.knows_wallet(&sender_id));
+        assert!(!replicas.store_exists_on_disk(&sender_id).await);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn self_check_of_healthy_wallets() -> Result<()> {
+        let (replicas, secret_key_set) = get_replicas().await?;
+        for _ in 0..2 {
+            let credit_proof = get_credit_proof(10, get_random_pk(), &secret_key_set)?;
+            let _ = replicas
+                .receive_propagated(XorName::random(), &credit_proof)
+                .await?;
+        }
+
+        let report = replicas.self_check().await?;
+        assert_eq!(report.wallets.len(), 2);
+        assert!(report.is_healthy());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn all_balances_skips_unloadable_wallets() -> Result<()> {
+        let (replicas, secret_key_set) = get_replicas().await?;
+        let mut expected = BTreeMap::new();
+        for amount in 1..4 {
+            let id = get_random_pk();
+            let credit_proof = get_credit_proof(amount, id, &secret_key_set)?;
+            let _ = replicas
+                .receive_propagated(XorName::random(), &credit_proof)
+                .await?;
+            let _ = expected.insert(id, Token::from_nano(amount));
+        }
+        assert_eq!(replicas.all_balances().await?, expected);
+
+        let corrupt = get_random_pk();
+        let credit_proof = get_credit_proof(10, corrupt, &secret_key_set)?;
+        let _ = replicas
+            .receive_propagated(XorName::random(), &credit_proof)
+            .await?;
+        let mut credit_proof = get_credit_proof(10, corrupt, &secret_key_set)?;
+        credit_proof.debiting_replicas_sig = Signature::Bls(SecretKey::random().sign(b"tampered"));
+        insert_event(&replicas, corrupt, propagated(credit_proof)).await?;
+
+        assert_eq!(replicas.all_balances().await?, expected);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn latest_events_counts_events_per_wallet() -> Result<()> {
+        let (replicas, secret_key_set) = get_replicas().await?;
+        let sender = SecretKey::random();
+        let sender_id = PublicKey::from(sender.public_key());
+        let recipient = get_random_pk();
+        for _ in 0..2 {
+            let credit_proof = get_credit_proof(10, sender_id, &secret_key_set)?;
+            let _ = replicas
+                .receive_propagated(XorName::random(), &credit_proof)
+                .await?;
+        }
+        let credit_proof = get_credit_proof(10, recipient, &secret_key_set)?;
+        let _ = replicas
+            .receive_propagated(XorName::random(), &credit_proof)
+            .await?;
+        let transfer = get_signed_transfer(&sender, 0, 5, recipient)?;
+        let _ = replicas.validate(transfer).await?;
+
+        let counts = replicas.latest_events().await?;
+        assert_eq!(counts.len(), 2);
+        assert_eq!(counts.get(&sender_id), Some(&3));
+        assert_eq!(counts.get(&recipient), Some(&1));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn audit_of_clean_history_passes() -> Result<()> {
+        let (replicas, secret_key_set) = get_replicas().await?;
+        let sender = SecretKey::random();
+        let sender_id = PublicKey::from(sender.public_key());
+        let credit_proof = get_credit_proof(10, sender_id, &secret_key_set)?;
+        let _ = replicas
+            .receive_propagated(XorName::random(), &credit_proof)
+            .await?;
+        let transfer = get_signed_transfer(&sender, 0, 4, get_random_pk())?;
+        let _ = replicas.validate(transfer.clone()).await?;
+        let _ = replicas
+            .register(&get_transfer_proof(transfer, &secret_key_set)?)
+            .await?;
+
+        let report = replicas.audit_wallet(sender_id).await?;
+        assert!(report.is_consistent());
+        assert_eq!(report.events, 3);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn audit_reports_first_inconsistent_event() -> Result<()> {
+        let (replicas, secret_key_set) = get_replicas().await?;
+        let sender = SecretKey::random();
+        let sender_id = PublicKey::from(sender.public_key());
+        let credit_proof = get_credit_proof(10, sender_id, &secret_key_set)?;
+        let _ = replicas
+            .receive_propagated(XorName::random(), &credit_proof)
+            .await?;
+        // an overspend, validly signed, but written around the replicas
+        let overspend = get_signed_transfer(&sender, 0, 20, get_random_pk())?;
+        let validation = get_validation(&overspend, &secret_key_set, 0)?;
+        insert_event(
+            &replicas,
+            sender_id,
+            ReplicaEvent::TransferValidated(validation),
+        )
+        .await?;
+
+        let report = replicas.audit_wallet(sender_id).await?;
+        assert_eq!(report.events, 2);
+        assert!(matches!(report.first_inconsistency, Some((1, _))));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn audit_passes_after_key_rotation() -> Result<()> {
+        let (mut replicas, secret_key_set) = get_replicas().await?;
+        let sender = SecretKey::random();
+        let sender_id = PublicKey::from(sender.public_key());
+        let credit_proof = get_credit_proof(10, sender_id, &secret_key_set)?;
+        let _ = replicas
+            .receive_propagated(XorName::random(), &credit_proof)
+            .await?;
+        let transfer = get_signed_transfer(&sender, 0, 4, get_random_pk())?;
+        let _ = replicas.validate(transfer.clone()).await?;
+        let _ = replicas
+            .register(&get_transfer_proof(transfer, &secret_key_set)?)
+            .await?;
+
+        let _ = rotate_keys(&mut replicas, &secret_key_set)?;
+        let report = replicas.audit_wallet(sender_id).await?;
+        assert!(report.is_consistent());
+        assert_eq!(report.events, 3);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn wallet_summary_matches_individual_queries() -> Result<()> {
+        let (replicas, secret_key_set) = get_replicas().await?;
+        let sender = SecretKey::random();
+        let sender_id = PublicKey::from(sender.public_key());
+        let credit_proof = get_credit_proof(10, sender_id, &secret_key_set)?;
+        let _ = replicas
+            .receive_propagated(XorName::random(), &credit_proof)
+            .await?;
+        let transfer = get_signed_transfer(&sender, 0, 4, get_random_pk())?;
+        let _ = replicas.validate(transfer.clone()).await?;
+        let _ = replicas
+            .register(&get_transfer_proof(transfer, &secret_key_set)?)
+            .await?;
+
+        let summary = replicas.wallet_summary(sender_id).await?;
+        assert_eq!(summary.balance, replicas.balance(sender_id).await?);
+        assert_eq!(
+            Some(&summary.event_count),
+            replicas.latest_events().await?.get(&sender_id)
+        );
+        assert_eq!(summary.last_event_kind, Some(EventKind::Registered));
+
+        let unknown = replicas.wallet_summary(get_random_pk()).await?;
+        assert_eq!(unknown.balance, Token::from_nano(0));
+        assert_eq!(unknown.event_count, 0);
+        assert_eq!(unknown.last_event_kind, None);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn all_events_are_ordered_by_wallet() -> Result<()> {
+        let (replicas, secret_key_set) = get_replicas().await?;
+        let mut ids: Vec<_> = (0..5).map(|_| get_random_pk()).collect();
+        for id in &ids {
+            for amount in 1..3 {
+                let credit_proof = get_credit_proof(amount, *id, &secret_key_set)?;
+                let _ = replicas
+                    .receive_propagated(XorName::random(), &credit_proof)
+                    .await?;
+            }
+        }
+
+        let events = replicas.all_events().await?;
+        assert_eq!(events, replicas.all_events().await?);
+        ids.sort();
+        let mut expected = vec![];
+        for id in ids {
+            expected.extend(get_events(&replicas, id)?);
+        }
+        assert_eq!(events, expected);
+        Ok(())
     }
 
-    fn exists_in_chain(&self, key: &bls::PublicKey) -> bool {
-        self.info
-            .section_chain
-            .keys()
-            .any(|key_in_chain| key_in_chain == key)
+    #[tokio::test]
+    async fn force_propagates_dropped_credit_once() -> Result<()> {
+        let (replicas, secret_key_set) = get_replicas().await?;
+        let recipient = get_random_pk();
+        let credit_proof = get_credit_proof(10, recipient, &secret_key_set)?;
+
+        for _ in 0..2 {
+            let _ = replicas.force_propagate(credit_proof.clone()).await?;
+        }
+        assert_eq!(replicas.balance(recipient).await?, Token::from_nano(10));
+        assert_eq!(get_events(&replicas, recipient)?.len(), 1);
+        Ok(())
     }
 
-    async fn get_load_or_create_store(
-        &self,
-        id: PublicKey,
-    ) -> Result<Arc<Mutex<TransferStore<ReplicaEvent>>>> {
-        let self_lock = self.self_lock.lock().await;
-        // get or create the store for PK.
-        let key_lock = match self.load_key_lock(id).await {
-            Ok(lock) => lock,
-            Err(_) => {
-                let store = match TransferStore::new(id.into(), &self.root_dir) {
-                    Ok(store) => store,
-                    // no key lock, so we create one for this payout...
-                    Err(_e) => TransferStore::new(id.into(), &self.root_dir)?,
-                };
-                let locked_store = Arc::new(Mutex::new(store));
-                let _ = self.locks.insert(id, locked_store.clone());
-                locked_store
+    #[tokio::test]
+    async fn force_propagate_rejects_unverified_proof() -> Result<()> {
+        let (replicas, secret_key_set) = get_replicas().await?;
+        let recipient = get_random_pk();
+        let mut credit_proof = get_credit_proof(10, recipient, &secret_key_set)?;
+        credit_proof.debiting_replicas_sig = Signature::Bls(SecretKey::random().sign(b"tampered"));
+
+        assert!(matches!(
+            replicas.force_propagate(credit_proof).await,
+            Err(Error::InvalidPropagatedTransfer(_))
+        ));
+        assert!(!replicas.store_exists_on_disk(&recipient).await);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn prunes_only_empty_settled_wallets() -> Result<()> {
+        for policy in &[PrunePolicy::Untrack, PrunePolicy::Delete] {
+            let (replicas, secret_key_set) = get_replicas().await?;
+            let funded = get_random_pk();
+            let sender = SecretKey::random();
+            let emptied = PublicKey::from(sender.public_key());
+            for id in &[funded, emptied] {
+                let credit_proof = get_credit_proof(10, *id, &secret_key_set)?;
+                let _ = replicas
+                    .receive_propagated(XorName::random(), &credit_proof)
+                    .await?;
+            }
+            let transfer = get_signed_transfer(&sender, 0, 10, funded)?;
+            let _ = replicas.validate(transfer.clone()).await?;
+            let _ = replicas
+                .register(&get_transfer_proof(transfer, &secret_key_set)?)
+                .await?;
+
+            // a wallet that never had any transfers
+            let unused = get_random_pk();
+            replicas.import_history(unused, vec![]).await?;
+
+            let pruned = replicas.prune_empty_wallets(*policy).await?;
+            assert_eq!(pruned, vec![unused]);
+            assert!(replicas.knows_wallet(&funded));
+            assert!(!replicas.knows_wallet(&unused));
+            // a wallet with debits is kept, so its history is still handed over,
+            // and its debit counter is never reset
+            assert!(replicas.knows_wallet(&emptied));
+            assert!(replicas.user_wallets().await.contains_key(&emptied));
+            assert!(replicas.store_exists_on_disk(&emptied).await);
+            assert_eq!(
+                replicas.store_exists_on_disk(&unused).await,
+                *policy == PrunePolicy::Untrack
+            );
+            assert_eq!(replicas.balance(funded).await?, Token::from_nano(10));
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn prune_skips_wallets_that_cannot_be_loaded() -> Result<()> {
+        let (replicas, secret_key_set) = get_replicas().await?;
+        let corrupt = get_random_pk();
+        let credit_proof = get_credit_proof(10, corrupt, &secret_key_set)?;
+        let _ = replicas
+            .receive_propagated(XorName::random(), &credit_proof)
+            .await?;
+        let mut forged = get_credit_proof(10, corrupt, &secret_key_set)?;
+        forged.debiting_replicas_sig = Signature::Bls(SecretKey::random().sign(b"forged"));
+        insert_event(&replicas, corrupt, propagated(forged)).await?;
+        let unused = get_random_pk();
+        replicas.import_history(unused, vec![]).await?;
+
+        let pruned = replicas.prune_empty_wallets(PrunePolicy::Delete).await?;
+        assert_eq!(pruned, vec![unused]);
+        assert!(replicas.knows_wallet(&corrupt));
+        assert!(replicas.store_exists_on_disk(&corrupt).await);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn concurrent_loads_match_serial_loads() -> Result<()> {
+        let (mut replicas, secret_key_set) = get_replicas().await?;
+        for amount in 1..=40 {
+            let credit_proof = get_credit_proof(amount, get_random_pk(), &secret_key_set)?;
+            let _ = replicas
+                .receive_propagated(XorName::random(), &credit_proof)
+                .await?;
+        }
+
+        replicas.set_load_concurrency(1);
+        let serial_events = replicas.all_events().await?;
+        let serial_amount = replicas.managed_amount().await?;
+        replicas.set_load_concurrency(8);
+        assert_eq!(replicas.all_events().await?, serial_events);
+        assert_eq!(replicas.managed_amount().await?, serial_amount);
+        assert_eq!(serial_events.len(), 40);
+        assert_eq!(serial_amount, Token::from_nano((1..=40).sum()));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn store_loads_overlap_up_to_load_concurrency() -> Result<()> {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        let (mut replicas, secret_key_set) = get_replicas().await?;
+        for amount in 1..=16 {
+            let credit_proof = get_credit_proof(amount, get_random_pk(), &secret_key_set)?;
+            let _ = replicas
+                .receive_propagated(XorName::random(), &credit_proof)
+                .await?;
+        }
+
+        for concurrency in &[1, 8] {
+            replicas.set_load_concurrency(*concurrency);
+            let in_flight = Arc::new(AtomicUsize::new(0));
+            let max_in_flight = Arc::new(AtomicUsize::new(0));
+            let results = replicas
+                .query_all_stores({
+                    let in_flight = in_flight.clone();
+                    let max_in_flight = max_in_flight.clone();
+                    move |store| {
+                        let loads = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                        let _ = max_in_flight.fetch_max(loads, Ordering::SeqCst);
+                        std::thread::sleep(std::time::Duration::from_millis(20));
+                        let _ = in_flight.fetch_sub(1, Ordering::SeqCst);
+                        store.len()
+                    }
+                })
+                .await;
+            assert_eq!(results.len(), 16);
+            let max_in_flight = max_in_flight.load(Ordering::SeqCst);
+            if *concurrency == 1 {
+                assert_eq!(max_in_flight, 1);
+            } else {
+                assert!(max_in_flight > 1 && max_in_flight <= *concurrency);
+            }
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn durability_applies_to_all_wallet_stores() -> Result<()> {
+        let (mut replicas, secret_key_set) = get_replicas().await?;
+        let tracked = get_random_pk();
+        let later = get_random_pk();
+        let credit_proof = get_credit_proof(10, tracked, &secret_key_set)?;
+        let _ = replicas
+            .receive_propagated(XorName::random(), &credit_proof)
+            .await?;
+
+        replicas.set_durability(DurabilityMode::Batched(3)).await;
+        for id in &[tracked, later] {
+            let credit_proof = get_credit_proof(10, *id, &secret_key_set)?;
+            let _ = replicas
+                .receive_propagated(XorName::random(), &credit_proof)
+                .await?;
+            let key_lock = replicas.load_key_lock(*id).await?;
+            assert_eq!(key_lock.lock().await.pending_syncs(), 1);
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn section_split_retains_wallets_of_our_prefix() -> Result<()> {
+        let (mut replicas, secret_key_set) = get_replicas().await?;
+        let ids: Vec<_> = (0..10).map(|_| get_random_pk()).collect();
+        for id in &ids {
+            let credit_proof = get_credit_proof(10, *id, &secret_key_set)?;
+            let _ = replicas
+                .receive_propagated(XorName::random(), &credit_proof)
+                .await?;
+        }
+        let our_prefix = Prefix::default().pushed(false);
+        let ours = |id: &PublicKey| our_prefix.matches(&XorName::from(*id));
+
+        let retained = replicas.handle_section_split(our_prefix).await?;
+        assert_eq!(retained, ids.iter().filter(|id| ours(id)).count());
+        for id in &ids {
+            assert_eq!(replicas.knows_wallet(id), ours(id));
+            assert!(replicas.store_exists_on_disk(id).await);
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn exports_verified_wallet_events() -> Result<()> {
+        let (replicas, secret_key_set) = get_replicas().await?;
+        let sender = SecretKey::random();
+        let sender_id = PublicKey::from(sender.public_key());
+        let credit_proof = get_credit_proof(10, sender_id, &secret_key_set)?;
+        let _ = replicas
+            .receive_propagated(XorName::random(), &credit_proof)
+            .await?;
+        let transfer = get_signed_transfer(&sender, 0, 4, get_random_pk())?;
+        let _ = replicas.validate(transfer).await?;
+
+        let exported = replicas.export_wallet(sender_id).await?;
+        assert_eq!(exported.len(), 2);
+        assert_eq!(exported, get_events(&replicas, sender_id)?);
+
+        let unknown = get_random_pk();
+        assert!(replicas.export_wallet(unknown).await?.is_empty());
+        assert!(!replicas.store_exists_on_disk(&unknown).await);
+
+        let mut credit_proof = get_credit_proof(10, sender_id, &secret_key_set)?;
+        credit_proof.debiting_replicas_sig = Signature::Bls(SecretKey::random().sign(b"tampered"));
+        insert_event(&replicas, sender_id, propagated(credit_proof)).await?;
+        assert!(replicas.export_wallet(sender_id).await.is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn managed_amount_skips_wallets_that_fail_verification() -> Result<()> {
+        let (replicas, secret_key_set) = get_replicas().await?;
+        let corrupt = get_random_pk();
+        for id in &[corrupt, get_random_pk()] {
+            let credit_proof = get_credit_proof(10, *id, &secret_key_set)?;
+            let _ = replicas
+                .receive_propagated(XorName::random(), &credit_proof)
+                .await?;
+        }
+        let mut forged = get_credit_proof(10, corrupt, &secret_key_set)?;
+        forged.debiting_replicas_sig = Signature::Bls(SecretKey::random().sign(b"forged"));
+        insert_event(&replicas, corrupt, propagated(forged)).await?;
+
+        assert_eq!(replicas.managed_amount().await?, Token::from_nano(10));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn managed_amount_reports_overflow() -> Result<()> {
+        let (replicas, secret_key_set) = get_replicas().await?;
+        let credit_proof = get_credit_proof(u64::MAX - 1, get_random_pk(), &secret_key_set)?;
+        let _ = replicas
+            .receive_propagated(XorName::random(), &credit_proof)
+            .await?;
+        let credit_proof = get_credit_proof(1, get_random_pk(), &secret_key_set)?;
+        let _ = replicas
+            .receive_propagated(XorName::random(), &credit_proof)
+            .await?;
+        assert_eq!(replicas.managed_amount().await?, Token::from_nano(u64::MAX));
+
+        let credit_proof = get_credit_proof(1, get_random_pk(), &secret_key_set)?;
+        let _ = replicas
+            .receive_propagated(XorName::random(), &credit_proof)
+            .await?;
+        assert!(matches!(
+            replicas.managed_amount().await,
+            Err(Error::TokenOverflow)
+        ));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn balance_comes_with_latest_credit_proof() -> Result<()> {
+        let (replicas, secret_key_set) = get_replicas().await?;
+        let wallet_id = get_random_pk();
+        assert_eq!(
+            replicas.balance_and_proof(wallet_id).await?,
+            (Token::from_nano(0), None)
+        );
+
+        let mut latest = None;
+        for amount in &[10, 5] {
+            let credit_proof = get_credit_proof(*amount, wallet_id, &secret_key_set)?;
+            let _ = replicas
+                .receive_propagated(XorName::random(), &credit_proof)
+                .await?;
+            latest = Some(credit_proof);
+        }
+        assert_eq!(
+            replicas.balance_and_proof(wallet_id).await?,
+            (Token::from_nano(15), latest)
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn events_since_returns_the_tail() -> Result<()> {
+        let (replicas, secret_key_set) = get_replicas().await?;
+        let wallet_id = get_random_pk();
+        for amount in 1..5 {
+            let credit_proof = get_credit_proof(amount, wallet_id, &secret_key_set)?;
+            let _ = replicas
+                .receive_propagated(XorName::random(), &credit_proof)
+                .await?;
+        }
+        let events = get_events(&replicas, wallet_id)?;
+
+        assert_eq!(replicas.events_since(wallet_id, 0).await?, events);
+        assert_eq!(replicas.events_since(wallet_id, 3).await?, events[3..]);
+        assert!(replicas.events_since(wallet_id, 4).await?.is_empty());
+        assert!(replicas.events_since(wallet_id, 5).await.is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn reads_for_peers_never_pass_on_tampered_events() -> Result<()> {
+        let (replicas, secret_key_set) = get_replicas().await?;
+        let healthy = get_random_pk();
+        let corrupt = get_random_pk();
+        for id in &[healthy, corrupt] {
+            let credit_proof = get_credit_proof(10, *id, &secret_key_set)?;
+            let _ = replicas
+                .receive_propagated(XorName::random(), &credit_proof)
+                .await?;
+        }
+        let mut credit_proof = get_credit_proof(10, corrupt, &secret_key_set)?;
+        credit_proof.debiting_replicas_sig = Signature::Bls(SecretKey::random().sign(b"tampered"));
+        insert_event(&replicas, corrupt, propagated(credit_proof)).await?;
+
+        assert!(matches!(
+            replicas.history(corrupt).await,
+            Err(Error::CorruptStore(_))
+        ));
+        assert!(matches!(
+            replicas.events_since(corrupt, 1).await,
+            Err(Error::CorruptStore(_))
+        ));
+        assert_eq!(
+            replicas.all_events().await?,
+            get_events(&replicas, healthy)?
+        );
+        assert!(replicas.export_snapshot(vec![]).await.is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn self_check_reports_corrupt_store() -> Result<()> {
+        let (replicas, secret_key_set) = get_replicas().await?;
+        let healthy = get_random_pk();
+        let corrupt = get_random_pk();
+        for id in &[healthy, corrupt] {
+            let credit_proof = get_credit_proof(10, *id, &secret_key_set)?;
+            let _ = replicas
+                .receive_propagated(XorName::random(), &credit_proof)
+                .await?;
+        }
+        let mut credit_proof = get_credit_proof(10, corrupt, &secret_key_set)?;
+        credit_proof.debiting_replicas_sig = Signature::Bls(SecretKey::random().sign(b"tampered"));
+        insert_event(&replicas, corrupt, propagated(credit_proof)).await?;
+
+        let report = replicas.self_check().await?;
+        assert!(!report.is_healthy());
+        assert_eq!(report.wallets.get(&healthy), Some(&WalletHealth::Healthy));
+        assert!(matches!(
+            report.wallets.get(&corrupt),
+            Some(WalletHealth::Corrupt(_))
+        ));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn resigns_pending_transfers_after_rotation() -> Result<()> {
+        let (mut replicas, secret_key_set) = get_replicas().await?;
+        let sender = SecretKey::random();
+        let sender_id = PublicKey::from(sender.public_key());
+        let credit_proof = get_credit_proof(10, sender_id, &secret_key_set)?;
+        let _ = replicas
+            .receive_propagated(XorName::random(), &credit_proof)
+            .await?;
+        let transfer = get_signed_transfer(&sender, 0, 10, get_random_pk())?;
+        let original = replicas.validate(transfer).await?;
+
+        let new_key_set = rotate_keys(&mut replicas, &secret_key_set)?;
+        assert_eq!(replicas.resign_pending().await?, 1);
+
+        match get_events(&replicas, sender_id)?.last() {
+            Some(ReplicaEvent::TransferValidated(e)) => {
+                assert_eq!(e.signed_debit, original.signed_debit);
+                assert_eq!(e.signed_credit, original.signed_credit);
+                assert_eq!(e.replicas, new_key_set.public_keys());
+                assert!(is_valid_event(
+                    &replicas.info.section_chain,
+                    &ReplicaEvent::TransferValidated(e.clone())
+                )?);
             }
+            other => return Err(Error::Logic(format!("Unexpected event: {:?}", other))),
+        }
+        // nothing left to re-sign
+        assert_eq!(replicas.resign_pending().await?, 0);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn verifies_stored_events_only_once() -> Result<()> {
+        let (replicas, secret_key_set) = get_replicas().await?;
+        let wallet_id = get_random_pk();
+        for amount in 1..=3 {
+            let credit_proof = get_credit_proof(amount, wallet_id, &secret_key_set)?;
+            let _ = replicas
+                .receive_propagated(XorName::random(), &credit_proof)
+                .await?;
+        }
+
+        // each receive verified only the events appended before it
+        let key_lock = replicas.load_key_lock(wallet_id).await?;
+        assert_eq!(key_lock.lock().await.verified(), 2);
+        assert_eq!(replicas.balance(wallet_id).await?, Token::from_nano(6));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn repairs_unloadable_store() -> Result<()> {
+        let (replicas, secret_key_set) = get_replicas().await?;
+        let wallet_id = get_random_pk();
+        let path = TransferStore::<ReplicaEvent>::db_path(wallet_id.into(), &replicas.root_dir)?;
+        std::fs::create_dir_all(replicas.root_dir.join(TRANSFERS_DIR_NAME))?;
+        std::fs::write(&path, b"torn")?;
+        assert!(matches!(
+            replicas.wallet_summary(wallet_id).await,
+            Err(Error::CorruptStore(_))
+        ));
+        assert!(matches!(
+            replicas.balance(wallet_id).await,
+            Err(Error::CorruptStore(_))
+        ));
+
+        assert_eq!(replicas.repair_store(wallet_id).await?, 4);
+        assert_eq!(replicas.wallet_summary(wallet_id).await?.event_count, 0);
+
+        // the loaded store of a tracked wallet is never repaired
+        let credit_proof = get_credit_proof(10, wallet_id, &secret_key_set)?;
+        let _ = replicas
+            .receive_propagated(XorName::random(), &credit_proof)
+            .await?;
+        assert!(replicas.repair_store(wallet_id).await.is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn fetches_credit_proof_by_id() -> Result<()> {
+        let (replicas, secret_key_set) = get_replicas().await?;
+        let wallet_id = get_random_pk();
+        let credit_proof = get_credit_proof(10, wallet_id, &secret_key_set)?;
+        let _ = replicas
+            .receive_propagated(XorName::random(), &credit_proof)
+            .await?;
+
+        let fetched = replicas
+            .credit_proof_for(wallet_id, *credit_proof.id())
+            .await?;
+        assert_eq!(fetched, credit_proof);
+
+        match replicas
+            .credit_proof_for(wallet_id, XorName::random().0)
+            .await
+        {
+            Err(Error::NoSuchCredit(_)) => Ok(()),
+            other => Err(Error::Logic(format!("Unexpected result: {:?}", other))),
+        }
+    }
+
+    #[tokio::test]
+    async fn replayed_credits_do_not_load_the_wallet() -> Result<()> {
+        let (replicas, secret_key_set) = get_replicas().await?;
+        let wallet_id = get_random_pk();
+        let credit_proof = get_credit_proof(10, wallet_id, &secret_key_set)?;
+        let _ = replicas
+            .receive_propagated(XorName::random(), &credit_proof)
+            .await?;
+
+        // any further load of the wallet would now fail
+        let mut tampered = get_credit_proof(10, wallet_id, &secret_key_set)?;
+        tampered.debiting_replicas_sig = Signature::Bls(SecretKey::random().sign(b"tampered"));
+        let key_lock = replicas.load_key_lock(wallet_id).await?;
+        key_lock.lock().await.try_insert(propagated(tampered))?;
+
+        for _ in 0..10 {
+            let _ = replicas
+                .receive_propagated(XorName::random(), &credit_proof)
+                .await?;
+        }
+
+        // a replay is only answered when its proof is valid
+        let mut forged = credit_proof.clone();
+        forged.debiting_replicas_sig = Signature::Bls(SecretKey::random().sign(b"forged"));
+        match replicas
+            .receive_propagated(XorName::random(), &forged)
+            .await
+        {
+            Err(Error::InvalidPropagatedTransfer(_)) => (),
+            other => return Err(Error::Logic(format!("Unexpected result: {:?}", other))),
+        }
+
+        // a new credit is not short-circuited
+        let new_credit = get_credit_proof(5, wallet_id, &secret_key_set)?;
+        match replicas
+            .receive_propagated(XorName::random(), &new_credit)
+            .await
+        {
+            Err(Error::CorruptStore(_)) => Ok(()),
+            other => Err(Error::Logic(format!("Unexpected result: {:?}", other))),
+        }
+    }
+
+    #[test]
+    fn recent_credits_evict_the_least_recently_used() {
+        let mut recent = RecentCredits::default();
+        let ids: Vec<CreditId> = (0..=RECENT_CREDITS_CAPACITY)
+            .map(|_| XorName::random().0)
+            .collect();
+        for id in &ids[..RECENT_CREDITS_CAPACITY] {
+            recent.insert(*id);
+        }
+        // a replay of the oldest id makes it the most recently used
+        recent.insert(ids[0]);
+        recent.insert(ids[RECENT_CREDITS_CAPACITY]);
+        assert!(recent.contains(&ids[0]));
+        assert!(!recent.contains(&ids[1]));
+        assert!(recent.contains(&ids[RECENT_CREDITS_CAPACITY]));
+    }
+
+    #[tokio::test]
+    async fn failed_write_leaves_wallet_unchanged() -> Result<()> {
+        let (replicas, secret_key_set) = get_replicas().await?;
+        let wallet_id = get_random_pk();
+        let first = get_credit_proof(10, wallet_id, &secret_key_set)?;
+        let _ = replicas
+            .receive_propagated(XorName::random(), &first)
+            .await?;
+
+        // writes fail while the store directory is missing
+        let transfers_dir = replicas.root_dir.join(TRANSFERS_DIR_NAME);
+        std::fs::remove_dir_all(&transfers_dir)?;
+        let second = get_credit_proof(5, wallet_id, &secret_key_set)?;
+        assert!(replicas
+            .receive_propagated(XorName::random(), &second)
+            .await
+            .is_err());
+
+        std::fs::create_dir_all(&transfers_dir)?;
+        let _ = replicas
+            .receive_propagated(XorName::random(), &second)
+            .await?;
+
+        assert_eq!(
+            get_events(&replicas, wallet_id)?,
+            vec![propagated(first), propagated(second)]
+        );
+        assert_eq!(replicas.balance(wallet_id).await?, Token::from_nano(15));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn snapshot_round_trips() -> Result<()> {
+        let (replicas, secret_key_set) = get_replicas().await?;
+        let sender = SecretKey::random();
+        let sender_id = PublicKey::from(sender.public_key());
+        let recipient_id = get_random_pk();
+        for (id, amount) in &[(sender_id, 10), (recipient_id, 3)] {
+            let credit_proof = get_credit_proof(*amount, *id, &secret_key_set)?;
+            let _ = replicas
+                .receive_propagated(XorName::random(), &credit_proof)
+                .await?;
+        }
+        let transfer = get_signed_transfer(&sender, 0, 4, recipient_id)?;
+        let _ = replicas.validate(transfer).await?;
+
+        let mut snapshot = vec![];
+        replicas.export_snapshot(&mut snapshot).await?;
+
+        let (mut restored, _) = get_replicas().await?;
+        restored.info = replicas.info.clone();
+        assert_eq!(restored.import_snapshot(snapshot.as_slice()).await?, 2);
+
+        for id in &[sender_id, recipient_id] {
+            assert_eq!(restored.balance(*id).await?, replicas.balance(*id).await?);
+            assert_eq!(get_events(&restored, *id)?, get_events(&replicas, *id)?);
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn conflicting_snapshot_imports_nothing() -> Result<()> {
+        let (replicas, secret_key_set) = get_replicas().await?;
+        let first_id = get_random_pk();
+        let second_id = get_random_pk();
+        for id in &[first_id, second_id] {
+            let credit_proof = get_credit_proof(10, *id, &secret_key_set)?;
+            let _ = replicas
+                .receive_propagated(XorName::random(), &credit_proof)
+                .await?;
+        }
+        let mut snapshot = vec![];
+        replicas.export_snapshot(&mut snapshot).await?;
+
+        // the wallet imported last conflicts with the snapshot
+        let (mut restored, _) = get_replicas().await?;
+        restored.info = replicas.info.clone();
+        let conflicting_id = first_id.max(second_id);
+        let other_id = first_id.min(second_id);
+        let credit_proof = get_credit_proof(5, conflicting_id, &secret_key_set)?;
+        let _ = restored
+            .receive_propagated(XorName::random(), &credit_proof)
+            .await?;
+
+        assert!(restored.import_snapshot(snapshot.as_slice()).await.is_err());
+        assert!(!TransferStore::<ReplicaEvent>::exists(
+            other_id.into(),
+            &restored.root_dir
+        )?);
+        assert_eq!(get_events(&restored, conflicting_id)?.len(), 1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn crediting_key_follows_replica_keys() -> Result<()> {
+        let (mut replicas, secret_key_set) = get_replicas().await?;
+        assert_eq!(
+            replicas.crediting_key(),
+            PublicKey::Bls(secret_key_set.public_keys().public_key())
+        );
+
+        let new_key_set = rotate_keys(&mut replicas, &secret_key_set)?;
+        assert_eq!(
+            replicas.crediting_key(),
+            PublicKey::Bls(new_key_set.public_keys().public_key())
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn verifies_transfer_proofs() -> Result<()> {
+        let (replicas, secret_key_set) = get_replicas().await?;
+        let sender = SecretKey::random();
+        let transfer = get_signed_transfer(&sender, 0, 10, get_random_pk())?;
+
+        let valid = get_transfer_proof(transfer.clone(), &secret_key_set)?;
+        replicas.verify_transfer_proof(&valid)?;
+
+        let unknown_keys = SecretKeySet::random(0, &mut thread_rng());
+        let unknown = get_transfer_proof(transfer, &unknown_keys)?;
+        assert!(replicas.verify_transfer_proof(&unknown).is_err());
+
+        let mut broken = valid;
+        broken.debit_sig = Signature::Bls(SecretKey::random().sign(b"tampered"));
+        assert!(replicas.verify_transfer_proof(&broken).is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn balance_at_historical_points() -> Result<()> {
+        let (replicas, secret_key_set) = get_replicas().await?;
+        let sender = SecretKey::random();
+        let sender_id = PublicKey::from(sender.public_key());
+        for amount in &[10, 5] {
+            let credit_proof = get_credit_proof(*amount, sender_id, &secret_key_set)?;
+            let _ = replicas
+                .receive_propagated(XorName::random(), &credit_proof)
+                .await?;
+        }
+        let transfer = get_signed_transfer(&sender, 0, 4, get_random_pk())?;
+        let _ = replicas.validate(transfer.clone()).await?;
+        let _ = replicas
+            .register(&get_transfer_proof(transfer, &secret_key_set)?)
+            .await?;
+
+        assert_eq!(
+            replicas.balance_at(sender_id, 0).await?,
+            Token::from_nano(0)
+        );
+        assert_eq!(
+            replicas.balance_at(sender_id, 1).await?,
+            Token::from_nano(10)
+        );
+        assert_eq!(
+            replicas.balance_at(sender_id, 2).await?,
+            Token::from_nano(15)
+        );
+        assert_eq!(
+            replicas.balance_at(sender_id, 4).await?,
+            Token::from_nano(11)
+        );
+        assert_eq!(
+            replicas.balance_at(sender_id, 4).await?,
+            replicas.balance(sender_id).await?
+        );
+        assert!(replicas.balance_at(sender_id, 5).await.is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn quorum_size_follows_threshold() -> Result<()> {
+        let (replicas, secret_key_set) = get_replicas().await?;
+        assert_eq!(replicas.quorum_size(), secret_key_set.threshold() + 1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn exposes_key_index_and_threshold() -> Result<()> {
+        let root_dir = TempDir::new("root")?.into_path();
+        let secret_key_set = SecretKeySet::random(2, &mut thread_rng());
+        let peer_replicas = secret_key_set.public_keys();
+        let secret_key_share = secret_key_set.secret_key_share(1);
+        let info = ReplicaInfo {
+            id: secret_key_share.public_key_share(),
+            key_index: 1,
+            peer_replicas: peer_replicas.clone(),
+            section_chain: SectionChain::new(peer_replicas.public_key()),
+            signing: TestReplicaSigning::new(secret_key_share, 1, peer_replicas),
         };
-        let _ = self_lock.overflowing_add(0); // resolve: is a usage at end of block necessary to actually engage the lock?
+        let replicas = Replicas::new(root_dir, info, BTreeMap::new()).await?;
+        assert_eq!(replicas.key_index(), 1);
+        assert_eq!(replicas.threshold(), 2);
+        assert_eq!(replicas.quorum_size(), 3);
+        Ok(())
+    }
 
-        Ok(key_lock)
+    #[tokio::test]
+    async fn rejects_inconsistent_replica_info() -> Result<()> {
+        let secret_key_set = SecretKeySet::random(1, &mut thread_rng());
+        let section_chain = SectionChain::new(secret_key_set.public_keys().public_key());
+
+        let mut info = get_replica_info(&secret_key_set, section_chain.clone());
+        info.key_index = 5;
+        assert!(matches!(
+            Replicas::in_memory(info, BTreeMap::new()).await,
+            Err(Error::InvalidReplicaInfo(_))
+        ));
+
+        let other_chain = SectionChain::new(SecretKey::random().public_key());
+        let info = get_replica_info(&secret_key_set, other_chain);
+        assert!(matches!(
+            Replicas::in_memory(info, BTreeMap::new()).await,
+            Err(Error::InvalidReplicaInfo(_))
+        ));
+
+        let info = get_replica_info(&secret_key_set, section_chain.clone());
+        let mut replicas = Replicas::in_memory(info, BTreeMap::new()).await?;
+
+        // updates are checked as well, and leave the info as it was when rejected
+        let other_key_set = SecretKeySet::random(1, &mut thread_rng());
+        let info = get_replica_info(&other_key_set, section_chain);
+        assert!(matches!(
+            replicas.update_replica_info(info),
+            Err(Error::InvalidReplicaInfo(_))
+        ));
+        assert_eq!(
+            replicas.crediting_key(),
+            PublicKey::Bls(secret_key_set.public_keys().public_key())
+        );
+        Ok(())
     }
 
-    // ------------------------------------------------------------------
-    //  --------------------  Simulated Payouts ------------------------
-    // ------------------------------------------------------------------
+    #[test]
+    fn combines_a_quorum_of_validations() -> Result<()> {
+        let secret_key_set = SecretKeySet::random(1, &mut thread_rng());
+        let sender = SecretKey::random();
+        let transfer = get_signed_transfer(&sender, 0, 10, get_random_pk())?;
+        let validations = (0..3)
+            .map(|index| get_validation(&transfer, &secret_key_set, index))
+            .collect::<Result<Vec<_>>>()?;
 
-    #[cfg(feature = "simulated-payouts")]
-    pub async fn credit_without_proof(&self, transfer: Transfer) -> Result<NodeDuty> {
-        debug!("Performing credit without proof");
+        // below threshold
+        assert!(combine_signatures(&validations[..1]).is_err());
 
-        let debit = transfer.debit();
+        let proof = combine_signatures(&validations[..2])?;
+        let public_key = secret_key_set.public_keys().public_key();
+        assert!(verify_sig(
+            &secret_key_set.public_keys(),
+            &proof.debit_sig,
+            &proof.signed_debit
+        )?);
+        assert!(verify_sig(
+            &secret_key_set.public_keys(),
+            &proof.credit_sig,
+            &proof.signed_credit
+        )?);
+        assert_eq!(proof.debiting_replicas_keys.public_key(), public_key);
+        Ok(())
+    }
 
-        debug!("provided debit {:?}", debit);
-        let credit = transfer.credit()?;
+    #[test]
+    fn rejects_validations_with_invalid_shares() -> Result<()> {
+        let secret_key_set = SecretKeySet::random(1, &mut thread_rng());
+        let sender = SecretKey::random();
+        let transfer = get_signed_transfer(&sender, 0, 10, get_random_pk())?;
+        let mut validations = (0..3)
+            .map(|index| get_validation(&transfer, &secret_key_set, index))
+            .collect::<Result<Vec<_>>>()?;
+        // a share signed by another replica than the one it claims to be of
+        validations[1].replica_credit_sig.index = 2;
 
-        debug!("provided credit {:?}", credit);
+        match combine_signatures(&validations) {
+            Err(Error::InvalidOperation(_)) => (),
+            other => return Err(Error::Logic(format!("Unexpected result: {:?}", other))),
+        }
+        let _ = combine_signatures(&[validations[0].clone(), validations[2].clone()])?;
+        Ok(())
+    }
 
-        // Acquire lock of the wallet.
-        let id = transfer.to;
+    #[tokio::test]
+    async fn reads_of_a_busy_wallet_wait_for_it() -> Result<()> {
+        use futures::FutureExt;
+        let (replicas, secret_key_set) = get_replicas().await?;
+        let wallet_id = get_random_pk();
+        let credit_proof = get_credit_proof(10, wallet_id, &secret_key_set)?;
+        let _ = replicas
+            .receive_propagated(XorName::random(), &credit_proof)
+            .await?;
 
-        let store = self.get_load_or_create_store(id).await?;
-        let mut store = store.lock().await;
+        let key_lock = replicas.load_key_lock(wallet_id).await?;
+        let store = key_lock.lock().await;
+        // the read neither fails, nor reads the store around the lock
+        assert!(replicas.history(wallet_id).now_or_never().is_none());
+        drop(store);
 
-        let mut wallet = self.load_wallet(&store, OwnerType::Single(id)).await?;
+        assert_eq!(
+            replicas.history(wallet_id).await?.credits,
+            vec![credit_proof]
+        );
+        assert!(replicas.user_wallets().await.contains_key(&wallet_id));
+        Ok(())
+    }
 
-        debug!("wallet loaded");
-        wallet.credit_without_proof(credit.clone())?;
+    #[tokio::test]
+    async fn runs_transfer_cycle_in_memory() -> Result<()> {
+        let secret_key_set = SecretKeySet::random(0, &mut thread_rng());
+        let section_chain = SectionChain::new(secret_key_set.public_keys().public_key());
+        let info = get_replica_info(&secret_key_set, section_chain);
+        let replicas = Replicas::in_memory(info, BTreeMap::new()).await?;
 
-        // let debit_store = self.get_load_or_create_store(debit.id().actor).await?;
-        // let mut debit_store = debit_store.lock().await;
-        // // Access to the specific wallet is now serialised!
-        // let mut debit_wallet = self.load_wallet(&debit_store, debit.id().actor).await?;
-        // debit_wallet.debit_without_proof(debit.clone())?;
+        let sender = SecretKey::random();
+        let sender_id = PublicKey::from(sender.public_key());
+        let recipient = get_random_pk();
+        let credit_proof = get_credit_proof(10, sender_id, &secret_key_set)?;
+        let _ = replicas
+            .receive_propagated(XorName::random(), &credit_proof)
+            .await?;
+        let transfer = get_signed_transfer(&sender, 0, 4, recipient)?;
+        let _ = replicas.validate(transfer.clone()).await?;
+        let transfer_proof = get_transfer_proof(transfer, &secret_key_set)?;
+        let _ = replicas.register(&transfer_proof).await?;
+        let _ = replicas
+            .receive_propagated(XorName::random(), &transfer_proof.credit_proof())
+            .await?;
 
-        let dummy_msg = "DUMMY MSG";
+        assert_eq!(replicas.balance(sender_id).await?, Token::from_nano(6));
+        assert_eq!(replicas.balance(recipient).await?, Token::from_nano(4));
+        assert_eq!(replicas.history(sender_id).await?.debits.len(), 1);
+        assert_eq!(replicas.all_events().await?.len(), 4);
+        assert!(replicas.self_check().await?.is_healthy());
+        assert!(!replicas.store_exists_on_disk(&sender_id).await);
+        Ok(())
+    }
+
+    async fn get_replicas() -> Result<(Replicas<TestReplicaSigning>, SecretKeySet)> {
+        let root_dir = TempDir::new("root")?.into_path();
         let mut rng = thread_rng();
-        let sec_key_set = SecretKeySet::random(7, &mut rng);
-        let replica_keys = sec_key_set.public_keys();
-        let sec_key = SecretKey::random();
-        let sig = sec_key.sign(dummy_msg);
-        let transfer_proof = TransferAgreementProof {
-            signed_credit: SignedCredit {
-                credit,
-                actor_signature: Signature::from(sig.clone()),
-            },
-            signed_debit: SignedDebit {
+        let secret_key_set = SecretKeySet::random(0, &mut rng);
+        let section_chain = SectionChain::new(secret_key_set.public_keys().public_key());
+        let info = get_replica_info(&secret_key_set, section_chain);
+        let replicas = Replicas::new(root_dir, info, BTreeMap::new()).await?;
+        Ok((replicas, secret_key_set))
+    }
+
+    fn get_replica_info(
+        secret_key_set: &SecretKeySet,
+        section_chain: SectionChain,
+    ) -> ReplicaInfo<TestReplicaSigning> {
+        let peer_replicas = secret_key_set.public_keys();
+        let secret_key_share = secret_key_set.secret_key_share(0);
+        ReplicaInfo {
+            id: secret_key_share.public_key_share(),
+            key_index: 0,
+            peer_replicas: peer_replicas.clone(),
+            section_chain,
+            signing: TestReplicaSigning::new(secret_key_share, 0, peer_replicas),
+        }
+    }
+
+    /// Extends the section chain of the replicas with a new key set.
+    fn rotate_keys(
+        replicas: &mut Replicas<TestReplicaSigning>,
+        secret_key_set: &SecretKeySet,
+    ) -> Result<SecretKeySet> {
+        let new_key_set = SecretKeySet::random(0, &mut thread_rng());
+        let new_key = new_key_set.public_keys().public_key();
+        let signature = sign_bls(secret_key_set, &new_key)?;
+        let mut section_chain = replicas.info.section_chain.clone();
+        section_chain
+            .insert(
+                &secret_key_set.public_keys().public_key(),
+                new_key,
+                signature,
+            )
+            .map_err(|e| Error::Logic(format!("{:?}", e)))?;
+        replicas.update_replica_info(get_replica_info(&new_key_set, section_chain))?;
+        Ok(new_key_set)
+    }
+
+    fn get_random_pk() -> PublicKey {
+        PublicKey::from(SecretKey::random().public_key())
+    }
+
+    fn propagated(credit_proof: CreditAgreementProof) -> ReplicaEvent {
+        ReplicaEvent::TransferPropagated(TransferPropagated { credit_proof })
+    }
+
+    /// Appends the event to the tracked store of the wallet, around the checks of the replicas.
+    async fn insert_event(
+        replicas: &Replicas<TestReplicaSigning>,
+        id: PublicKey,
+        event: ReplicaEvent,
+    ) -> Result<()> {
+        let key_lock = replicas.load_key_lock(id).await?;
+        let mut store = key_lock.lock().await;
+        store.try_insert(event)
+    }
+
+    fn get_events(
+        replicas: &Replicas<TestReplicaSigning>,
+        id: PublicKey,
+    ) -> Result<Vec<ReplicaEvent>> {
+        Ok(TransferStore::open_read_only(id.into(), &replicas.root_dir)?.get_all())
+    }
+
+    /// A transfer signed by the actor `sender`.
+    fn get_signed_transfer(
+        sender: &SecretKey,
+        counter: u64,
+        amount: u64,
+        recipient: PublicKey,
+    ) -> Result<SignedTransfer> {
+        let transfer = Transfer {
+            debit_id: DebitId::new(PublicKey::from(sender.public_key()), counter),
+            amount: Token::from_nano(amount),
+            to: recipient,
+            msg: "transfer".to_string(),
+        };
+        let debit = transfer.debit();
+        let credit = transfer.credit()?;
+        let debit_signature = Signature::Bls(sender.sign(utils::serialise(&debit)?));
+        let credit_signature = Signature::Bls(sender.sign(utils::serialise(&credit)?));
+        Ok(SignedTransfer {
+            debit: SignedDebit {
                 debit,
-                actor_signature: Signature::from(sig.clone()),
+                actor_signature: debit_signature,
+            },
+            credit: SignedCredit {
+                credit,
+                actor_signature: credit_signature,
             },
-            debit_sig: Signature::from(sig.clone()),
-            credit_sig: Signature::from(sig),
-            debiting_replicas_keys: replica_keys,
+        })
+    }
+
+    /// A credit signed by the (threshold 0) replicas of `secret_key_set`.
+    fn get_credit_proof(
+        amount: u64,
+        recipient: PublicKey,
+        secret_key_set: &SecretKeySet,
+    ) -> Result<CreditAgreementProof> {
+        let credit = Credit {
+            id: XorName::random().0,
+            amount: Token::from_nano(amount),
+            recipient,
+            msg: "credit".to_string(),
+        };
+        let actor_signature = sign(secret_key_set, &credit)?;
+        let signed_credit = SignedCredit {
+            credit,
+            actor_signature,
         };
+        let debiting_replicas_sig = sign(secret_key_set, &signed_credit)?;
+        Ok(CreditAgreementProof {
+            signed_credit,
+            debiting_replicas_sig,
+            debiting_replicas_keys: secret_key_set.public_keys(),
+        })
+    }
 
-        store.try_insert(ReplicaEvent::TransferPropagated(TransferPropagated {
-            credit_proof: transfer_proof.credit_proof(),
-        }))?;
+    /// A validation of the transfer by the replica at `index`.
+    fn get_validation(
+        signed_transfer: &SignedTransfer,
+        secret_key_set: &SecretKeySet,
+        index: usize,
+    ) -> Result<TransferValidated> {
+        let secret_key_share = secret_key_set.secret_key_share(index);
+        let replica_debit_sig = SignatureShare {
+            index,
+            share: secret_key_share.sign(utils::serialise(&signed_transfer.debit)?),
+        };
+        let replica_credit_sig = SignatureShare {
+            index,
+            share: secret_key_share.sign(utils::serialise(&signed_transfer.credit)?),
+        };
+        Ok(TransferValidated {
+            signed_debit: signed_transfer.debit.clone(),
+            signed_credit: signed_transfer.credit.clone(),
+            replica_debit_sig,
+            replica_credit_sig,
+            replicas: secret_key_set.public_keys(),
+        })
+    }
 
-        Ok(NodeDuty::NoOp)
+    /// An agreement proof of the transfer, signed by the
+    /// (threshold 0) replicas of `secret_key_set`.
+    fn get_transfer_proof(
+        signed_transfer: SignedTransfer,
+        secret_key_set: &SecretKeySet,
+    ) -> Result<TransferAgreementProof> {
+        Ok(TransferAgreementProof {
+            debit_sig: sign(secret_key_set, &signed_transfer.debit)?,
+            credit_sig: sign(secret_key_set, &signed_transfer.credit)?,
+            signed_debit: signed_transfer.debit,
+            signed_credit: signed_transfer.credit,
+            debiting_replicas_keys: secret_key_set.public_keys(),
+        })
+    }
+
+    fn sign<T: Serialize>(secret_key_set: &SecretKeySet, data: &T) -> Result<Signature> {
+        Ok(Signature::Bls(sign_bls(secret_key_set, data)?))
+    }
+
+    /// Combines the signature shares of a quorum of the replicas of `secret_key_set`.
+    fn sign_bls<T: Serialize>(secret_key_set: &SecretKeySet, data: &T) -> Result<bls::Signature> {
+        let data = utils::serialise(data)?;
+        let shares: BTreeMap<_, _> = (0..=secret_key_set.threshold())
+            .map(|index| (index, secret_key_set.secret_key_share(index).sign(&data)))
+            .collect();
+        secret_key_set
+            .public_keys()
+            .combine_signatures(&shares)
+            .map_err(|_| Error::CouldNotCombineSignatures)
     }
 }
@@ -6,32 +6,330 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use crate::{to_db_key::ToDbKey, utils, Error, Result};
-use pickledb::PickleDb;
+use crate::{
+    to_db_key::{from_db_key, ToDbKey},
+    utils, Error, Result,
+};
+use log::warn;
+use pickledb::{PickleDb, PickleDbDumpPolicy, SerializationMethod};
 use serde::{de::DeserializeOwned, Serialize};
-use std::{fmt::Debug, marker::PhantomData, path::Path};
+use sn_data_types::{CreditId, DebitId, ReplicaEvent};
+use std::{
+    collections::HashMap,
+    fmt::Debug,
+    fs::{self, File},
+    marker::PhantomData,
+    path::{Path, PathBuf},
+};
 use xor_name::XorName;
 
-const TRANSFERS_DIR_NAME: &str = "transfers";
+pub(crate) const TRANSFERS_DIR_NAME: &str = "transfers";
 const DB_EXTENSION: &str = ".db";
+const IN_MEMORY_DB_NAME: &str = "in_memory.db";
+const BACKUP_EXTENSION: &str = ".bak";
+
+/// Identifies an event among the events of a store.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum EventId {
+    /// A validated debit.
+    Validated(DebitId),
+    /// A registered debit.
+    Registered(DebitId),
+    /// A propagated credit.
+    Propagated(CreditId),
+}
+
+/// Lets the store index events, and detect
+/// events that conflict with already stored ones.
+pub trait StoreEvent {
+    /// The id the event is indexed by, if any.
+    fn event_id(&self) -> Option<EventId>;
+}
+
+impl StoreEvent for ReplicaEvent {
+    fn event_id(&self) -> Option<EventId> {
+        use ReplicaEvent::*;
+        match self {
+            TransferValidated(e) => Some(EventId::Validated(e.signed_debit.id())),
+            TransferRegistered(e) => Some(EventId::Registered(e.transfer_proof.id())),
+            TransferPropagated(e) => Some(EventId::Propagated(*e.credit_proof.id())),
+            _ => None,
+        }
+    }
+}
+
+/// How durably a store persists the events appended to it.
+///
+/// Every append writes the db file, so an event survives a crash of the
+/// process in either mode. Only a sync to disk makes it survive a power loss.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DurabilityMode {
+    /// Sync to disk after every append. An event that was appended
+    /// survives a power loss, at the cost of a sync per append.
+    Always,
+    /// Sync to disk after every n appends, and when the store is dropped.
+    /// Up to n - 1 appended events can be lost on a power loss.
+    Batched(usize),
+}
+
+impl Default for DurabilityMode {
+    fn default() -> Self {
+        Self::Always
+    }
+}
 
 /// Disk storage for transfers.
 pub struct TransferStore<TEvent: Debug + Serialize + DeserializeOwned> {
     db: PickleDb,
+    /// Position of events in the db, by their id.
+    index: HashMap<EventId, usize>,
+    /// Latest debit counters, by debit kind, which the next debit of the kind must follow.
+    debit_counters: DebitCounters,
+    /// Path of the db file, if on disk.
+    path: Option<PathBuf>,
+    durability: DurabilityMode,
+    /// Number of appends since the last sync to disk.
+    unsynced: usize,
+    /// Number of leading events verified since the store was opened.
+    verified: usize,
+    /// Syncs the db file at the path to disk.
+    sync: fn(&Path) -> Result<()>,
     _phantom: PhantomData<TEvent>,
 }
 
-impl<'a, TEvent: Debug + Serialize + DeserializeOwned> TransferStore<TEvent>
+impl<TEvent: Debug + Serialize + DeserializeOwned> Drop for TransferStore<TEvent> {
+    fn drop(&mut self) {
+        if self.unsynced > 0 {
+            if let Some(path) = &self.path {
+                if let Err(error) = (self.sync)(path) {
+                    warn!("Could not sync transfer store on drop: {}", error);
+                }
+            }
+        }
+    }
+}
+
+impl<'a, TEvent: Debug + Serialize + DeserializeOwned + StoreEvent> TransferStore<TEvent>
 where
     TEvent: 'a,
 {
+    /// Loads the store for the id from disk, or creates it if it doesn't exist.
+    /// Events from the first one that cannot be deserialised, e.g. after an
+    /// interrupted write, are discarded, once the file is backed up next to the store.
+    /// A file that cannot be loaded at all is never overwritten, but
+    /// fails with `Error::CorruptStore`, and is left for `repair`.
     pub fn new(id: XorName, root_dir: &Path) -> Result<Self> {
         let db_dir = root_dir.join(Path::new(TRANSFERS_DIR_NAME));
         let db_name = format!("{}{}", id.to_db_key()?, DB_EXTENSION);
-        Ok(Self {
-            db: utils::new_auto_dump_db(db_dir.as_path(), db_name)?,
+        let path = Self::db_path(id, root_dir)?;
+        let db = if path.exists() {
+            let mut db = PickleDb::load_bin(&path, PickleDbDumpPolicy::AutoDump)
+                .map_err(|error| Error::CorruptStore(format!("{}: {}", path.display(), error)))?;
+            if let Some(first_invalid) = first_invalid_event::<TEvent>(&db) {
+                let _ = discard_tail(&mut db, &path, first_invalid)?;
+            }
+            db
+        } else {
+            utils::new_auto_dump_db(db_dir.as_path(), db_name)?
+        };
+        let mut store = Self {
+            db,
+            index: HashMap::new(),
+            debit_counters: DebitCounters::default(),
+            path: Some(path),
+            durability: DurabilityMode::default(),
+            unsynced: 0,
+            verified: 0,
+            sync: sync_to_disk,
             _phantom: PhantomData::default(),
-        })
+        };
+        store.rebuild_index();
+        Ok(store)
+    }
+
+    /// Loads the store for the id from disk, for reading only.
+    /// Nothing is ever written to disk through it, so it can be
+    /// used while the store is also opened for writing.
+    /// Events that `new` would discard are skipped.
+    pub fn open_read_only(id: XorName, root_dir: &Path) -> Result<Self> {
+        let path = Self::db_path(id, root_dir)?;
+        let mut db = PickleDb::load_read_only(&path, SerializationMethod::Bin)
+            .map_err(|error| Error::CorruptStore(format!("{}: {}", path.display(), error)))?;
+        if let Some(first_invalid) = first_invalid_event::<TEvent>(&db) {
+            warn!(
+                "Skipping events of transfer store {} from event {}, which cannot be deserialised.",
+                path.display(),
+                first_invalid
+            );
+            remove_from(&mut db, first_invalid)?;
+        }
+        let mut store = Self {
+            db,
+            index: HashMap::new(),
+            debit_counters: DebitCounters::default(),
+            path: None,
+            durability: DurabilityMode::default(),
+            unsynced: 0,
+            verified: 0,
+            sync: sync_to_disk,
+            _phantom: PhantomData::default(),
+        };
+        store.rebuild_index();
+        Ok(store)
+    }
+
+    fn rebuild_index(&mut self) {
+        self.index = self
+            .get_all()
+            .iter()
+            .enumerate()
+            .filter_map(|(position, event)| event.event_id().map(|id| (id, position)))
+            .collect();
+        self.debit_counters = DebitCounters::default();
+        for id in self.index.keys() {
+            self.debit_counters.record(id);
+        }
+    }
+
+    /// A store that keeps its events in memory only, e.g. for tests and
+    /// ephemeral nodes. Nothing is ever written to disk.
+    pub fn in_memory() -> Self {
+        Self {
+            db: PickleDb::new_bin(IN_MEMORY_DB_NAME, PickleDbDumpPolicy::NeverDump),
+            index: HashMap::new(),
+            debit_counters: DebitCounters::default(),
+            path: None,
+            durability: DurabilityMode::default(),
+            unsynced: 0,
+            verified: 0,
+            sync: sync_to_disk,
+            _phantom: PhantomData::default(),
+        }
+    }
+
+    /// Sets how durably appended events are persisted.
+    pub fn set_durability(&mut self, durability: DurabilityMode) {
+        self.durability = durability;
+    }
+
+    /// Number of appends not yet synced to disk.
+    #[allow(unused)]
+    pub fn pending_syncs(&self) -> usize {
+        self.unsynced
+    }
+
+    /// Number of leading events that have been verified
+    /// since the store was opened.
+    pub(crate) fn verified(&self) -> usize {
+        self.verified
+    }
+
+    /// Marks the first `count` events as verified.
+    pub(crate) fn set_verified(&mut self, count: usize) {
+        self.verified = count;
+    }
+
+    /// Syncs all appended events to disk.
+    pub fn sync(&mut self) -> Result<()> {
+        if let Some(path) = &self.path {
+            (self.sync)(path)?;
+        }
+        self.unsynced = 0;
+        Ok(())
+    }
+
+    /// Syncs to disk if the durability mode requires it after a write.
+    fn written(&mut self) -> Result<()> {
+        self.unsynced += 1;
+        match self.durability {
+            DurabilityMode::Always => self.sync(),
+            DurabilityMode::Batched(n) if self.unsynced >= n => self.sync(),
+            DurabilityMode::Batched(_) => Ok(()),
+        }
+    }
+
+    /// Repairs a store that cannot be loaded, e.g. after an interrupted write,
+    /// by discarding the events from the first one that cannot be deserialised,
+    /// or all of them if the file cannot be loaded at all.
+    /// The file is first backed up next to the store, so nothing is lost.
+    /// Must not be called while the store is open.
+    /// Returns the number of bytes discarded.
+    pub fn repair(id: XorName, root_dir: &Path) -> Result<u64> {
+        let path = Self::db_path(id, root_dir)?;
+        if !path.exists() {
+            return Ok(0);
+        }
+        let size_before = fs::metadata(&path)?.len();
+        let first_invalid = match PickleDb::load_read_only(&path, SerializationMethod::Bin) {
+            Ok(db) => match first_invalid_event::<TEvent>(&db) {
+                Some(position) => Some(position),
+                None => return Ok(0),
+            },
+            Err(_) => None,
+        };
+        if let Some(first_invalid) = first_invalid {
+            let mut db = PickleDb::load_bin(&path, PickleDbDumpPolicy::DumpUponRequest)
+                .map_err(Error::PickleDb)?;
+            return discard_tail(&mut db, &path, first_invalid);
+        }
+        // the whole file is unreadable, so it is replaced by an empty store
+        let backup = backup_path(&path);
+        let _ = fs::copy(&path, &backup)?;
+        fs::remove_file(&path)?;
+        let db_dir = root_dir.join(Path::new(TRANSFERS_DIR_NAME));
+        let db_name = format!("{}{}", id.to_db_key()?, DB_EXTENSION);
+        let _ = utils::new_auto_dump_db(db_dir.as_path(), db_name)?;
+        sync_to_disk(&path)?;
+        warn!(
+            "Repaired transfer store {}: discarded all {} bytes, backed up to {}.",
+            path.display(),
+            size_before,
+            backup.display()
+        );
+        Ok(size_before)
+    }
+
+    /// Whether a store for the id exists on disk.
+    /// Unlike `new`, this never creates the store.
+    pub fn exists(id: XorName, root_dir: &Path) -> Result<bool> {
+        Ok(Self::db_path(id, root_dir)?.exists())
+    }
+
+    /// Removes the store for the id from disk, if it exists.
+    pub fn delete(id: XorName, root_dir: &Path) -> Result<()> {
+        let path = Self::db_path(id, root_dir)?;
+        if path.exists() {
+            fs::remove_file(path)?;
+        }
+        Ok(())
+    }
+
+    /// Ids of all stores on disk.
+    pub fn list(root_dir: &Path) -> Result<Vec<XorName>> {
+        let db_dir = root_dir.join(TRANSFERS_DIR_NAME);
+        if !db_dir.exists() {
+            return Ok(vec![]);
+        }
+        let mut ids = vec![];
+        for entry in fs::read_dir(db_dir)? {
+            let file_name = entry?.file_name();
+            let key = match file_name
+                .to_str()
+                .and_then(|name| name.strip_suffix(DB_EXTENSION))
+            {
+                Some(key) => key,
+                None => continue,
+            };
+            if let Ok(id) = from_db_key(key) {
+                ids.push(id);
+            }
+        }
+        Ok(ids)
+    }
+
+    pub(crate) fn db_path(id: XorName, root_dir: &Path) -> Result<PathBuf> {
+        let db_name = format!("{}{}", id.to_db_key()?, DB_EXTENSION);
+        Ok(root_dir.join(TRANSFERS_DIR_NAME).join(db_name))
     }
 
     ///
@@ -60,30 +358,227 @@ where
         events
     }
 
-    ///
+    /// Number of events in the store.
+    pub fn len(&self) -> usize {
+        self.db.total_keys()
+    }
+
+    /// Whether the store has no events.
+    #[allow(unused)]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Removes the events from position `len` on, e.g. to undo appends.
+    pub fn truncate(&mut self, len: usize) -> Result<()> {
+        let total = self.len();
+        if len >= total {
+            return Ok(());
+        }
+        for position in (len..total).rev() {
+            let _ = self.db.rem(&position.to_string())?;
+        }
+        self.rebuild_index();
+        self.verified = self.verified.min(len);
+        self.written()
+    }
+
+    /// The event with the id, looked up through an in-memory index.
+    pub fn get_by_id(&self, id: &EventId) -> Option<TEvent> {
+        let position = self.index.get(id)?;
+        self.db.get(&position.to_string())
+    }
+
+    /// Replaces the stored event at the index.
+    pub fn replace(&mut self, index: usize, event: TEvent) -> Result<()> {
+        let key = &index.to_string();
+        if !self.db.exists(key) {
+            return Err(Error::Logic(format!("No event at index: {}", index)));
+        }
+        let replaced: Option<TEvent> = self.db.get(key);
+        self.db.set(key, &event).map_err(Error::PickleDb)?;
+        if let Some(id) = event.event_id() {
+            let _ = self.index.insert(id, index);
+        }
+        // the replacement has not been verified
+        self.verified = self.verified.min(index);
+        if let Err(error) = self.written() {
+            // undo the replacement, so that a failed write leaves the store as before
+            if let Some(replaced) = replaced {
+                if let Err(undo_error) = self.db.set(key, &replaced) {
+                    // the replacement is then still in the db, as in its file
+                    self.rebuild_index();
+                    return Err(Error::CorruptStore(format!(
+                        "could not undo the replacement of event {} after: {}. Undo failed: {}",
+                        index, error, undo_error
+                    )));
+                }
+                self.rebuild_index();
+            }
+            return Err(error);
+        }
+        Ok(())
+    }
+
+    /// Appends the event, unless it conflicts with a stored event.
+    /// An event conflicts if it duplicates a stored event,
+    /// or is a debit that does not directly follow the stored debits of its kind.
+    /// A failed append is undone, unless that fails as well, in which case
+    /// the event stays stored, as it is on disk, and `Error::CorruptStore` is returned.
     pub fn try_insert(&mut self, event: TEvent) -> Result<()> {
-        let key = &self.db.total_keys().to_string();
+        let id = event.event_id();
+        if let Some(id) = &id {
+            if self.index.contains_key(id) || !self.debit_counters.is_next(id) {
+                return Err(Error::ConflictingEvent(format!("{:?}", event)));
+            }
+        }
+        let position = self.db.total_keys();
+        let key = &position.to_string();
         if self.db.exists(key) {
             return Err(Error::Logic(format!(
                 "Key exists: {}. Event: {:?}",
                 key, event
             )));
         }
-        self.db.set(key, &event).map_err(Error::PickleDb)
+        // a failed set is undone by the db itself, in memory and on disk
+        self.db.set(key, &event).map_err(Error::PickleDb)?;
+        let debit_counters = self.debit_counters;
+        if let Some(id) = &id {
+            let _ = self.index.insert(id.clone(), position);
+            self.debit_counters.record(id);
+        }
+        if let Err(error) = self.written() {
+            // undo the insert, so that an event is only ever stored once it is synced as required,
+            // and the insert can be retried
+            if let Err(undo_error) = self.db.rem(key) {
+                // the event is then still in the db, as in its file, and so stays indexed
+                return Err(Error::CorruptStore(format!(
+                    "could not undo the append of event {} after: {}. Undo failed: {}",
+                    position, error, undo_error
+                )));
+            }
+            if let Some(id) = &id {
+                let _ = self.index.remove(id);
+            }
+            self.debit_counters = debit_counters;
+            return Err(error);
+        }
+        Ok(())
+    }
+}
+
+/// Counters of the latest validated and registered debits of a store.
+#[derive(Clone, Copy, Debug, Default)]
+struct DebitCounters {
+    validated: Option<u64>,
+    registered: Option<u64>,
+}
+
+impl DebitCounters {
+    /// Whether the event with the id is the next of the stored debits of its kind.
+    /// Debit counters of a wallet are contiguous, starting at 0.
+    /// Credits have no order requirement.
+    fn is_next(&self, id: &EventId) -> bool {
+        let (latest, counter) = match id {
+            EventId::Validated(debit_id) => (self.validated, debit_id.counter),
+            EventId::Registered(debit_id) => (self.registered, debit_id.counter),
+            EventId::Propagated(_) => return true,
+        };
+        match latest {
+            Some(latest) => latest.checked_add(1) == Some(counter),
+            None => counter == 0,
+        }
+    }
+
+    fn record(&mut self, id: &EventId) {
+        let (latest, counter) = match id {
+            EventId::Validated(debit_id) => (&mut self.validated, debit_id.counter),
+            EventId::Registered(debit_id) => (&mut self.registered, debit_id.counter),
+            EventId::Propagated(_) => return,
+        };
+        *latest = (*latest).max(Some(counter));
+    }
+}
+
+/// Discards the events of the db from the first one that cannot be deserialised,
+/// once its file is backed up next to it. Returns the number of bytes discarded.
+fn discard_tail(db: &mut PickleDb, path: &Path, first_invalid: usize) -> Result<u64> {
+    let size_before = fs::metadata(path)?.len();
+    let backup = backup_path(path);
+    let _ = fs::copy(path, &backup)?;
+    remove_from(db, first_invalid)?;
+    db.dump()?;
+    sync_to_disk(path)?;
+    let discarded = size_before.saturating_sub(fs::metadata(path)?.len());
+    warn!(
+        "Repaired transfer store {}: discarded {} bytes from event {}, backed up to {}.",
+        path.display(),
+        discarded,
+        first_invalid,
+        backup.display()
+    );
+    Ok(discarded)
+}
+
+/// Removes the events of the db from the position on.
+fn remove_from(db: &mut PickleDb, position: usize) -> Result<()> {
+    for key in db.get_all() {
+        if matches!(key.parse::<usize>(), Ok(key_position) if key_position >= position) {
+            let _ = db.rem(&key)?;
+        }
+    }
+    Ok(())
+}
+
+/// Position of the first event that cannot be deserialised, if any.
+fn first_invalid_event<TEvent: DeserializeOwned>(db: &PickleDb) -> Option<usize> {
+    (0..db.total_keys()).find(|position| db.get::<TEvent>(&position.to_string()).is_none())
+}
+
+/// A path for a backup of the file, that does not yet exist.
+fn backup_path(path: &Path) -> PathBuf {
+    let mut attempt = 0;
+    loop {
+        let backup = PathBuf::from(format!(
+            "{}.{}{}",
+            path.display(),
+            attempt,
+            BACKUP_EXTENSION
+        ));
+        if !backup.exists() {
+            return backup;
+        }
+        attempt += 1;
     }
 }
 
+/// Syncs the file, and on unix also its directory entry, to disk.
+fn sync_to_disk(path: &Path) -> Result<()> {
+    File::open(path)?.sync_all()?;
+    // directories cannot be opened for syncing on all platforms
+    if cfg!(unix) {
+        if let Some(dir) = path.parent() {
+            File::open(dir)?.sync_all()?;
+        }
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod test {
-    use super::TransferStore;
+    use super::{DurabilityMode, EventId, TransferStore};
     use crate::{Error, Result};
     use bls::SecretKeySet;
     use bls::{PublicKeySet, SecretKey, SecretKeyShare};
     use sn_data_types::{
-        Credit, CreditAgreementProof, CreditId, PublicKey, ReplicaEvent, SignedCredit, Token,
-        TransferPropagated,
+        Credit, CreditAgreementProof, CreditId, Debit, DebitId, PublicKey, ReplicaEvent, Signature,
+        SignedCredit, SignedDebit, Token, TransferAgreementProof, TransferPropagated,
+        TransferRegistered,
+    };
+    use std::{
+        collections::BTreeMap,
+        path::{Path, PathBuf},
     };
-    use std::collections::BTreeMap;
     use tempdir::TempDir;
 
     #[test]
@@ -123,6 +618,456 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn rejects_conflicting_events() -> Result<()> {
+        let tmp_dir = TempDir::new("root")?;
+        let root_dir = tmp_dir.into_path();
+        let mut store = TransferStore::new(xor_name::XorName::random(), &root_dir)?;
+        let actor = get_random_pk();
+
+        // a valid sequence
+        store.try_insert(get_registered(actor, 0))?;
+        store.try_insert(get_registered(actor, 1))?;
+        // a duplicate
+        match store.try_insert(get_registered(actor, 1)) {
+            Err(Error::ConflictingEvent(_)) => (),
+            other => return Err(Error::Logic(format!("Unexpected: {:?}", other))),
+        }
+        // out of order
+        match store.try_insert(get_registered(actor, 0)) {
+            Err(Error::ConflictingEvent(_)) => (),
+            other => return Err(Error::Logic(format!("Unexpected: {:?}", other))),
+        }
+        // a gap in the sequence
+        match store.try_insert(get_registered(actor, 3)) {
+            Err(Error::ConflictingEvent(_)) => (),
+            other => return Err(Error::Logic(format!("Unexpected: {:?}", other))),
+        }
+
+        assert_eq!(store.get_all().len(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn index_finds_events_by_id() -> Result<()> {
+        let id = xor_name::XorName::random();
+        let tmp_dir = TempDir::new("root")?;
+        let root_dir = tmp_dir.into_path();
+        let mut store = TransferStore::new(id, &root_dir)?;
+        let actor = get_random_pk();
+
+        let first = get_registered(actor, 0);
+        store.try_insert(first.clone())?;
+        assert_eq!(
+            store.get_by_id(&EventId::Registered(DebitId::new(actor, 0))),
+            Some(first.clone())
+        );
+
+        let second = get_registered(actor, 1);
+        store.try_insert(second.clone())?;
+        assert_eq!(
+            store.get_by_id(&EventId::Registered(DebitId::new(actor, 1))),
+            Some(second)
+        );
+        assert_eq!(
+            store.get_by_id(&EventId::Registered(DebitId::new(actor, 2))),
+            None
+        );
+
+        // the index is rebuilt on load
+        let store = TransferStore::<ReplicaEvent>::new(id, &root_dir)?;
+        assert_eq!(
+            store.get_by_id(&EventId::Registered(DebitId::new(actor, 0))),
+            Some(first)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn truncate_undoes_appends() -> Result<()> {
+        let id = xor_name::XorName::random();
+        let root_dir = TempDir::new("root")?.into_path();
+        let mut store = TransferStore::new(id, &root_dir)?;
+        let actor = get_random_pk();
+        let first = get_registered(actor, 0);
+        store.try_insert(first.clone())?;
+        store.try_insert(get_registered(actor, 1))?;
+        store.try_insert(get_registered(actor, 2))?;
+
+        store.truncate(1)?;
+        assert_eq!(store.get_all(), vec![first.clone()]);
+        assert_eq!(
+            store.get_by_id(&EventId::Registered(DebitId::new(actor, 1))),
+            None
+        );
+        // the truncated debits can be appended again
+        let second = get_registered(actor, 1);
+        store.try_insert(second.clone())?;
+        drop(store);
+
+        let store = TransferStore::<ReplicaEvent>::new(id, &root_dir)?;
+        assert_eq!(store.get_all(), vec![first, second]);
+        Ok(())
+    }
+
+    #[test]
+    fn read_only_store_never_writes_to_disk() -> Result<()> {
+        let id = xor_name::XorName::random();
+        let root_dir = TempDir::new("root")?.into_path();
+        let mut store = TransferStore::new(id, &root_dir)?;
+        let actor = get_random_pk();
+        let first = get_registered(actor, 0);
+        store.try_insert(first.clone())?;
+
+        let read_only = TransferStore::<ReplicaEvent>::open_read_only(id, &root_dir)?;
+        assert_eq!(
+            read_only.get_by_id(&EventId::Registered(DebitId::new(actor, 0))),
+            Some(first.clone())
+        );
+        let second = get_registered(actor, 1);
+        store.try_insert(second.clone())?;
+
+        // dropping the stale handle does not undo the append
+        drop(read_only);
+        drop(store);
+        let store = TransferStore::<ReplicaEvent>::new(id, &root_dir)?;
+        assert_eq!(store.get_all(), vec![first, second]);
+        Ok(())
+    }
+
+    #[test]
+    fn in_memory_store_behaves_as_on_disk() -> Result<()> {
+        let mut store = TransferStore::<ReplicaEvent>::in_memory();
+        let actor = get_random_pk();
+        let first = get_registered(actor, 0);
+        let second = get_registered(actor, 1);
+        store.try_insert(first.clone())?;
+        store.try_insert(second.clone())?;
+        assert!(matches!(
+            store.try_insert(first.clone()),
+            Err(Error::ConflictingEvent(_))
+        ));
+        assert_eq!(store.get_all(), vec![first.clone(), second]);
+        assert_eq!(
+            store.get_by_id(&EventId::Registered(DebitId::new(actor, 0))),
+            Some(first)
+        );
+        assert!(!Path::new(super::IN_MEMORY_DB_NAME).exists());
+        Ok(())
+    }
+
+    #[test]
+    fn confirmed_appends_survive_a_power_loss_by_default() -> Result<()> {
+        let id = xor_name::XorName::random();
+        let root_dir = TempDir::new("root")?.into_path();
+        let mut store = TransferStore::new(id, &root_dir)?;
+        store.sync = record_sync;
+        let actor = get_random_pk();
+        let first = get_registered(actor, 0);
+        store.try_insert(first.clone())?;
+        assert_eq!(store.pending_syncs(), 0);
+
+        // a crash before the sync completes fails the append, and leaves no trace of it
+        let second = get_registered(actor, 1);
+        store.sync = failed_sync;
+        assert!(store.try_insert(second.clone()).is_err());
+        assert_eq!(store.get_all(), vec![first.clone()]);
+        assert_eq!(
+            store.get_by_id(&EventId::Registered(DebitId::new(actor, 1))),
+            None
+        );
+
+        // so the append can be retried
+        store.sync = record_sync;
+        store.try_insert(second.clone())?;
+        power_loss(store, id, &root_dir)?;
+        let store = TransferStore::<ReplicaEvent>::new(id, &root_dir)?;
+        assert_eq!(store.get_all(), vec![first, second]);
+        Ok(())
+    }
+
+    #[test]
+    fn failed_dump_leaves_store_as_before() -> Result<()> {
+        let id = xor_name::XorName::random();
+        let root_dir = TempDir::new("root")?.into_path();
+        let mut store = TransferStore::new(id, &root_dir)?;
+        let actor = get_random_pk();
+        let first = get_registered(actor, 0);
+        store.try_insert(first.clone())?;
+
+        let dir = root_dir.join(super::TRANSFERS_DIR_NAME);
+        let lost_dir = lost_dir(&dir);
+        std::fs::rename(&dir, &lost_dir)?;
+        let second = get_registered(actor, 1);
+        assert!(matches!(
+            store.try_insert(second.clone()),
+            Err(Error::PickleDb(_))
+        ));
+        assert_eq!(store.get_all(), vec![first.clone()]);
+
+        std::fs::rename(&lost_dir, &dir)?;
+        store.try_insert(second.clone())?;
+        drop(store);
+        let store = TransferStore::<ReplicaEvent>::new(id, &root_dir)?;
+        assert_eq!(store.get_all(), vec![first, second]);
+        Ok(())
+    }
+
+    #[test]
+    fn append_that_cannot_be_undone_stays_stored() -> Result<()> {
+        let id = xor_name::XorName::random();
+        let root_dir = TempDir::new("root")?.into_path();
+        let mut store = TransferStore::new(id, &root_dir)?;
+        let actor = get_random_pk();
+        let first = get_registered(actor, 0);
+        store.try_insert(first.clone())?;
+
+        // the sync fails, and so does the dump undoing the append
+        let second = get_registered(actor, 1);
+        store.sync = sync_losing_dir;
+        assert!(matches!(
+            store.try_insert(second.clone()),
+            Err(Error::CorruptStore(_))
+        ));
+        let dir = root_dir.join(super::TRANSFERS_DIR_NAME);
+        std::fs::rename(lost_dir(&dir), &dir)?;
+
+        // the store still matches its file, so the append is not retried, but the next one follows it
+        store.sync = record_sync;
+        assert!(matches!(
+            store.try_insert(second.clone()),
+            Err(Error::ConflictingEvent(_))
+        ));
+        let third = get_registered(actor, 2);
+        store.try_insert(third.clone())?;
+        assert_eq!(store.get_all(), vec![first, second, third]);
+        let events = store.get_all();
+        drop(store);
+        let store = TransferStore::<ReplicaEvent>::new(id, &root_dir)?;
+        assert_eq!(store.get_all(), events);
+        Ok(())
+    }
+
+    #[test]
+    fn batched_durability_syncs_every_n_appends() -> Result<()> {
+        let id = xor_name::XorName::random();
+        let root_dir = TempDir::new("root")?.into_path();
+        let mut store = TransferStore::new(id, &root_dir)?;
+        store.sync = record_sync;
+        store.set_durability(DurabilityMode::Batched(3));
+        let actor = get_random_pk();
+        let events: Vec<_> = (0..5)
+            .map(|counter| get_registered(actor, counter))
+            .collect();
+
+        for (appended, event) in events.iter().enumerate() {
+            store.try_insert(event.clone())?;
+            assert_eq!(store.pending_syncs(), (appended + 1) % 3);
+        }
+
+        // the appends after the last sync are lost on a power loss
+        power_loss(store, id, &root_dir)?;
+        let store = TransferStore::<ReplicaEvent>::new(id, &root_dir)?;
+        assert_eq!(store.get_all(), events[..3].to_vec());
+        Ok(())
+    }
+
+    #[test]
+    fn batched_durability_syncs_on_drop() -> Result<()> {
+        let id = xor_name::XorName::random();
+        let root_dir = TempDir::new("root")?.into_path();
+        let mut store = TransferStore::new(id, &root_dir)?;
+        store.sync = record_sync;
+        store.set_durability(DurabilityMode::Batched(3));
+        let event = get_registered(get_random_pk(), 0);
+        store.try_insert(event.clone())?;
+        assert_eq!(store.pending_syncs(), 1);
+        drop(store);
+
+        let store = TransferStore::<ReplicaEvent>::new(id, &root_dir)?;
+        power_loss(store, id, &root_dir)?;
+        let store = TransferStore::<ReplicaEvent>::new(id, &root_dir)?;
+        assert_eq!(store.get_all(), vec![event]);
+        Ok(())
+    }
+
+    /// Kills the process owning the store, i.e. without running any drop,
+    /// and then loses power, so that only what was synced, as recorded by
+    /// `record_sync`, remains on disk.
+    fn power_loss(
+        store: TransferStore<ReplicaEvent>,
+        id: xor_name::XorName,
+        root_dir: &Path,
+    ) -> Result<()> {
+        std::mem::forget(store);
+        let path = TransferStore::<ReplicaEvent>::db_path(id, root_dir)?;
+        let synced = synced_copy(&path);
+        if synced.exists() {
+            let _ = std::fs::copy(synced, path)?;
+        } else {
+            std::fs::remove_file(path)?;
+        }
+        Ok(())
+    }
+
+    /// Stands in for a sync to disk, keeping a copy of the db file as what survives
+    /// a power loss. The tests using it show when the store syncs, not that a sync
+    /// reaches the disk.
+    fn record_sync(path: &Path) -> Result<()> {
+        let _ = std::fs::copy(path, synced_copy(path))?;
+        Ok(())
+    }
+
+    /// Stands in for a crash during a sync to disk.
+    fn failed_sync(_: &Path) -> Result<()> {
+        Err(Error::Logic("Simulated sync failure".to_string()))
+    }
+
+    /// Stands in for a crash during a sync to disk, after which the directory
+    /// of the db file cannot be written to, so that dumping the db fails.
+    fn sync_losing_dir(path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            std::fs::rename(dir, lost_dir(dir))?;
+        }
+        failed_sync(path)
+    }
+
+    /// Path the directory is moved to, to make writes to it fail.
+    fn lost_dir(dir: &Path) -> PathBuf {
+        PathBuf::from(format!("{}.lost", dir.display()))
+    }
+
+    /// Path of the copy of the db file, as of its last sync to disk.
+    fn synced_copy(path: &Path) -> PathBuf {
+        PathBuf::from(format!("{}.synced", path.display()))
+    }
+
+    #[test]
+    fn repair_discards_invalid_tail_with_backup() -> Result<()> {
+        let id = xor_name::XorName::random();
+        let tmp_dir = TempDir::new("root")?;
+        let root_dir = tmp_dir.into_path();
+        let mut store = TransferStore::new(id, &root_dir)?;
+        let actor = get_random_pk();
+        let first = get_registered(actor, 0);
+        let second = get_registered(actor, 1);
+        store.try_insert(first.clone())?;
+        store.try_insert(second.clone())?;
+        // a partially written final record
+        store.db.set("2", &"truncated".to_string())?;
+        drop(store);
+
+        assert!(TransferStore::<ReplicaEvent>::repair(id, &root_dir)? > 0);
+        assert_eq!(backups(&root_dir)?, 1);
+
+        let mut store = TransferStore::<ReplicaEvent>::new(id, &root_dir)?;
+        assert_eq!(store.get_all(), vec![first, second]);
+        // the store can be appended to again
+        store.try_insert(get_registered(actor, 2))?;
+        assert_eq!(store.get_all().len(), 3);
+        // a valid store needs no repair
+        drop(store);
+        assert_eq!(TransferStore::<ReplicaEvent>::repair(id, &root_dir)?, 0);
+        assert_eq!(backups(&root_dir)?, 1);
+        Ok(())
+    }
+
+    #[test]
+    fn load_discards_invalid_tail_with_backup() -> Result<()> {
+        let id = xor_name::XorName::random();
+        let root_dir = TempDir::new("root")?.into_path();
+        let mut store = TransferStore::new(id, &root_dir)?;
+        let actor = get_random_pk();
+        let first = get_registered(actor, 0);
+        let second = get_registered(actor, 1);
+        store.try_insert(first.clone())?;
+        store.try_insert(second.clone())?;
+        // a partially written final record
+        store.db.set("2", &"truncated".to_string())?;
+        drop(store);
+
+        // reading only skips the record, and leaves the file as it is
+        let read_only = TransferStore::<ReplicaEvent>::open_read_only(id, &root_dir)?;
+        assert_eq!(read_only.get_all(), vec![first.clone(), second.clone()]);
+        assert_eq!(backups(&root_dir)?, 0);
+
+        let mut store = TransferStore::<ReplicaEvent>::new(id, &root_dir)?;
+        assert_eq!(store.get_all(), vec![first, second]);
+        assert_eq!(backups(&root_dir)?, 1);
+        // the store can be appended to again
+        store.try_insert(get_registered(actor, 2))?;
+        assert_eq!(store.get_all().len(), 3);
+        Ok(())
+    }
+
+    #[test]
+    fn unloadable_store_is_not_overwritten() -> Result<()> {
+        let id = xor_name::XorName::random();
+        let root_dir = TempDir::new("root")?.into_path();
+        let store = TransferStore::<ReplicaEvent>::new(id, &root_dir)?;
+        drop(store);
+        let path = TransferStore::<ReplicaEvent>::db_path(id, &root_dir)?;
+        std::fs::write(&path, b"torn")?;
+
+        assert!(matches!(
+            TransferStore::<ReplicaEvent>::new(id, &root_dir),
+            Err(Error::CorruptStore(_))
+        ));
+        assert_eq!(std::fs::read(&path)?, b"torn");
+
+        assert_eq!(TransferStore::<ReplicaEvent>::repair(id, &root_dir)?, 4);
+        assert_eq!(backups(&root_dir)?, 1);
+        assert!(TransferStore::<ReplicaEvent>::new(id, &root_dir)?.is_empty());
+        Ok(())
+    }
+
+    /// Number of backups of stores.
+    fn backups(root_dir: &Path) -> Result<usize> {
+        let dir = root_dir.join(super::TRANSFERS_DIR_NAME);
+        let mut count = 0;
+        for entry in std::fs::read_dir(dir)? {
+            if entry?
+                .path()
+                .to_string_lossy()
+                .ends_with(super::BACKUP_EXTENSION)
+            {
+                count += 1;
+            }
+        }
+        Ok(count)
+    }
+
+    /// A registered debit, with dummy signatures.
+    fn get_registered(actor: PublicKey, counter: u64) -> ReplicaEvent {
+        let sig = Signature::Bls(SecretKey::random().sign(b"dummy"));
+        let mut rng = rand::thread_rng();
+        let debit = Debit {
+            id: DebitId::new(actor, counter),
+            amount: Token::from_nano(1),
+        };
+        let credit = Credit {
+            id: xor_name::XorName::random().0,
+            amount: Token::from_nano(1),
+            recipient: get_random_pk(),
+            msg: "dummy".to_string(),
+        };
+        ReplicaEvent::TransferRegistered(TransferRegistered {
+            transfer_proof: TransferAgreementProof {
+                signed_debit: SignedDebit {
+                    debit,
+                    actor_signature: sig.clone(),
+                },
+                signed_credit: SignedCredit {
+                    credit,
+                    actor_signature: sig.clone(),
+                },
+                debit_sig: sig.clone(),
+                credit_sig: sig,
+                debiting_replicas_keys: SecretKeySet::random(0, &mut rng).public_keys(),
+            },
+        })
+    }
+
     fn get_random_pk() -> PublicKey {
         PublicKey::from(SecretKey::random().public_key())
     }
@@ -152,7 +1097,7 @@ mod test {
         println!("Aggregating actor signature..");
 
         // Combine shares to produce the main signature.
-        let actor_signature = sn_data_types::Signature::Bls(
+        let actor_signature = Signature::Bls(
             peer_replicas
                 .combine_signatures(&credit_sig_shares)
                 .map_err(|_| Error::CouldNotCombineSignatures)?,
@@ -173,7 +1118,7 @@ mod test {
 
         println!("Aggregating replica signature..");
 
-        let debiting_replicas_sig = sn_data_types::Signature::Bls(
+        let debiting_replicas_sig = Signature::Bls(
             peer_replicas
                 .combine_signatures(&credit_sig_shares)
                 .map_err(|_| Error::CouldNotCombineSignatures)?,